@@ -11,15 +11,42 @@ pub enum Error {
     Json{
         source: serde_json::Error
     },
+    ///Like `Json`, but knowing which field was being read
+    #[snafu(display("Json error: {}", source))]
+    JsonAt{
+        source: serde_path_to_error::Error<serde_json::Error>
+    },
 }
 
 pub async fn read<T,R>(r: &mut R) -> Result<T,Error>
     where T: serde::de::DeserializeOwned,
           R: AsyncRead + Unpin
+{
+    from_str(&read_string(r).await?)
+}
+
+pub async fn read_string<R>(r: &mut R) -> Result<String,Error>
+    where R: AsyncRead + Unpin
 {
     let mut json = String::new();
     r.read_to_string(&mut json).await.context(Io)?;
-    Ok(serde_json::de::from_str(&json).context(Json)?)
+    Ok(json)
+}
+
+///Deserializes `json`, with errors saying which field they happened in as well as where
+pub fn from_str<T>(json: &str) -> Result<T,Error>
+    where T: serde::de::DeserializeOwned
+{
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let t = serde_path_to_error::deserialize(&mut deserializer).context(JsonAt)?;
+    deserializer.end().context(Json)?;
+    Ok(t)
+}
+
+pub fn to_string_pretty<T>(t: &T) -> Result<String,Error>
+    where T: serde::Serialize
+{
+    serde_json::to_string_pretty(t).context(Json)
 }
 
 pub async fn write<T,W>(w: &mut W, t: &T) -> Result<(),Error>
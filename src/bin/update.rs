@@ -18,12 +18,18 @@ use sentry;
 use modpack_tool::{
     Result,
     mod_source::{ModpackConfig,IndirectableModpack},
+    pack_format::PackFormat,
+    optional_mods::Selection,
+    cmds::Outcome,
     error::prelude::*,
 };
 
 use snafu::Snafu;
 use slog::{Drain, Logger};
-use std::sync::{Arc, Mutex};
+use std::{
+    io::Read,
+    sync::{Arc, Mutex},
+};
 
 async fn load_hybrid_config() -> modpack_tool::Result<Option<ModpackConfig>>{
     #[derive(Debug,Snafu)]
@@ -36,9 +42,9 @@ async fn load_hybrid_config() -> modpack_tool::Result<Option<ModpackConfig>>{
         Zip{
             source: zip::result::ZipError,
         },
-        #[snafu(display("Json Error while opening hybrid config: {}", source))]
-        Json{
-            source: serde_json::Error,
+        #[snafu(display("Error while reading hybrid config: {}", source))]
+        Format{
+            source: modpack_tool::pack_format::Error,
         },
     }
 
@@ -50,11 +56,29 @@ async fn load_hybrid_config() -> modpack_tool::Result<Option<ModpackConfig>>{
         }
         other => other,
     }.context(Zip).erased()?;
-    let indirected: IndirectableModpack = serde_json::from_reader(zip_reader.by_name("config.json").context(Zip).erased()?).context(Json).erased()?;
+    let mut config = None;
+    for &format in PackFormat::ALL {
+        match zip_reader.by_name(format.hybrid_entry()) {
+            Ok(mut entry) => {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents).context(Io).erased()?;
+                config = Some((format, contents));
+                break;
+            }
+            Err(zip::result::ZipError::FileNotFound) => {}
+            Err(e) => return Err(e).context(Zip).erased(),
+        }
+    }
+    let (format, contents) = match config {
+        Some(config) => config,
+        None => return Err(zip::result::ZipError::FileNotFound).context(Zip).erased(),
+    };
+    let indirected = IndirectableModpack::parse(format, &contents).context(Format).erased()?;
     Ok(Some(indirected.resolve().await?))
 }
 
-async fn async_main() -> Result<()> {
+///Runs the tool, returning the process exit code
+async fn async_main() -> Result<i32> {
     let _sentry = sentry::init("https://0b0da309fa014d60b7b5e6a9da40529e@sentry.io/1207316");
     sentry::integrations::panic::register_panic_handler();
     let mut builder = env_logger::Builder::from_default_env();
@@ -75,16 +99,19 @@ async fn async_main() -> Result<()> {
     );
     let log = root.new(o!());
     let cmd_res = if let Ok(Some(pack)) = load_hybrid_config().await{
-        modpack_tool::cmds::update(pack,log).await
+        modpack_tool::cmds::update(pack, Selection{ ask: true, ..Selection::default() }, log).await.map(|()| Outcome::Done)
     }else{
         let command = modpack_tool::cmds::Args::from_args();
         command.dispatch(log).await 
     };
-    if let Err(e) = cmd_res {
-        println!("Error: {}", e);
-        sentry::integrations::failure::capture_fail(&e);
+    match cmd_res {
+        Ok(outcome) => Ok(outcome.exit_code()),
+        Err(e) => {
+            println!("Error: {}", e);
+            sentry::integrations::failure::capture_fail(&e);
+            Ok(1)
+        }
     }
-    Ok(())
 }
 
 fn main() ->  Result<()>  {
@@ -93,5 +120,6 @@ fn main() ->  Result<()>  {
     rt.spawn(async move {
         tx.send(async_main().await).unwrap();
     });
-    rt.block_on(rx).unwrap()
+    let code = rt.block_on(rx).unwrap()?;
+    std::process::exit(code)
 }
\ No newline at end of file
@@ -0,0 +1,215 @@
+//! Single file pack bundles, containing everything needed for an install without network access
+use crate::{
+    cache::{Cache as _, Cacheable},
+    download,
+    maven::{self, hash_writer::HashWriter},
+    mc_libs,
+    mod_source::ModpackConfig,
+    error::prelude::*,
+};
+use slog::Logger;
+use snafu::Snafu;
+use std::{
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const CONFIG_ENTRY: &str = "config.json";
+const LOCK_ENTRY: &str = "lock.json";
+const CACHE_PREFIX: &str = "cache/";
+
+#[derive(Debug,Snafu)]
+pub enum Error{
+    #[snafu(display("Error while caching {}: {}", item, source))]
+    Caching{
+        item: String,
+        source: crate::download::Error,
+    },
+    #[snafu(display("Error while caching forge's libraries: {}", source))]
+    CachingLibraries{
+        source: mc_libs::Error,
+    },
+    #[snafu(display("Cached file {} is outside of the cache directory", path))]
+    NotInCache{
+        path: String,
+    },
+    #[snafu(display("Bundle lists a file at {}, which would be outside of the cache directory", path))]
+    UnsafePath{
+        path: String,
+    },
+    #[snafu(display("Io error {} while reading {}", source, path))]
+    Reading{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Io error {} while writing {}", source, path))]
+    Writing{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Zip error {} in bundle entry {}", source, entry))]
+    Zip{
+        entry: String,
+        source: zip::result::ZipError,
+    },
+    #[snafu(display("Json error {} in bundle entry {}", source, entry))]
+    Json{
+        entry: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Bundle entry {} is corrupt (expected sha1 {}, got {})", entry, expected, actual))]
+    HashMismatch{
+        entry: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+///Lists every cached file in a bundle, along with its hash
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Lockfile {
+    pub files: Vec<LockedFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockedFile {
+    ///Path relative to the cache root, always '/' separated
+    pub path: String,
+    pub sha1: String,
+}
+
+pub fn cache_root() -> PathBuf {
+    app_dirs::get_app_root(app_dirs::AppDataType::UserCache, crate::APP_INFO).expect("Cache directory must be accesible")
+}
+
+fn relative_cache_path(path: &Path) -> Result<String, Error> {
+    let relative = path.strip_prefix(cache_root()).ok().context(NotInCache{path: path.display().to_string()})?;
+    let parts: Vec<_> = relative.iter().map(|part| part.to_string_lossy().into_owned()).collect();
+    Ok(parts.join("/"))
+}
+
+///Where in the cache at `root` the bundled file at `relative` goes.
+///
+///Only plain names are allowed in the path, so a bundle can't write outside of the cache
+fn cache_target(root: &Path, relative: &str) -> Result<PathBuf, Error> {
+    let mut target = root.to_path_buf();
+    for part in relative.split('/') {
+        //windows would split on these too
+        if part.contains('\\') {
+            return UnsafePath{path: relative}.fail();
+        }
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => target.push(name),
+            _ => return UnsafePath{path: relative}.fail(),
+        }
+    }
+    if !target.starts_with(root) {
+        return UnsafePath{path: relative}.fail();
+    }
+    Ok(target)
+}
+
+fn sha1_of(data: &[u8]) -> String {
+    let mut sha = HashWriter::new();
+    sha.write_all(data).expect("HashWriter can't fail");
+    sha.digest().to_string()
+}
+
+///Caches everything `pack` needs, then writes it all into a single zip at `out`, laid out like the cache
+pub async fn write_bundle(pack: ModpackConfig, out: PathBuf, manager: download::Manager, log: Logger) -> Result<(), Error> {
+    let forge = pack.forge_maven_artifact();
+    let mut cached = vec![
+        maven::Cache::with(forge.clone(), manager.clone(), log.clone()).await
+            .context(download::error::Cached)
+            .context(Caching{item: format!("forge {}", pack.forge)})?
+    ];
+    let libraries = mc_libs::cache_libraries(forge.clone(), manager.clone(), log.clone()).await.context(CachingLibraries)?;
+    if let Some(version) = mc_libs::minecraft_version(&forge).context(CachingLibraries)? {
+        cached.push(version.cached_path());
+    }
+    cached.extend(libraries.iter().map(Cacheable::cached_path));
+    for modd in pack.mods.iter().map(|entry| entry.source.clone()) {
+        let item = modd.describe();
+        cached.push(modd.cache(manager.clone(), log.clone()).await.context(Caching{item})?);
+    }
+
+    let out_path = out.display().to_string();
+    let file = std::fs::File::create(&out).context(Writing{path: out_path.clone()})?;
+    let mut writer = zip::write::ZipWriter::new(file);
+    let mut lockfile = Lockfile{ files: vec![] };
+
+    for path in cached {
+        let relative = relative_cache_path(&path)?;
+        let mut data = vec![];
+        std::fs::File::open(&path)
+            .and_then(|mut file| file.read_to_end(&mut data))
+            .context(Reading{path: path.display().to_string()})?;
+
+        let entry = format!("{}{}", CACHE_PREFIX, relative);
+        info!(log, "bundling cached file"; "entry" => entry.clone());
+        writer.start_file(entry.as_str(), zip::write::FileOptions::default()).context(Zip{entry: entry.clone()})?;
+        writer.write_all(&data[..]).context(Writing{path: out_path.clone()})?;
+        lockfile.files.push(LockedFile{
+            path: relative,
+            sha1: sha1_of(&data[..]),
+        });
+    }
+
+    writer.start_file(CONFIG_ENTRY, zip::write::FileOptions::default()).context(Zip{entry: CONFIG_ENTRY})?;
+    serde_json::to_writer_pretty(&mut writer, &pack).context(Json{entry: CONFIG_ENTRY})?;
+    writer.start_file(LOCK_ENTRY, zip::write::FileOptions::default()).context(Zip{entry: LOCK_ENTRY})?;
+    serde_json::to_writer_pretty(&mut writer, &lockfile).context(Json{entry: LOCK_ENTRY})?;
+    writer.finish().context(Zip{entry: LOCK_ENTRY})?;
+    Ok(())
+}
+
+///Verifies every file in the bundle at `path` against its lockfile, and copies them into the local cache.
+///
+///Returns the pack config stored in the bundle
+pub fn import_bundle(path: &Path, log: &Logger) -> Result<ModpackConfig, Error> {
+    let bundle_path = path.display().to_string();
+    let file = std::fs::File::open(path).context(Reading{path: bundle_path.clone()})?;
+    let mut archive = zip::read::ZipArchive::new(file).context(Zip{entry: bundle_path.clone()})?;
+
+    let lockfile: Lockfile = serde_json::from_reader(archive.by_name(LOCK_ENTRY).context(Zip{entry: LOCK_ENTRY})?)
+        .context(Json{entry: LOCK_ENTRY})?;
+
+    let root = cache_root();
+    for locked in lockfile.files {
+        let entry = format!("{}{}", CACHE_PREFIX, locked.path);
+        let mut data = vec![];
+        archive.by_name(entry.as_str()).context(Zip{entry: entry.clone()})?
+            .read_to_end(&mut data)
+            .context(Reading{path: entry.clone()})?;
+
+        let actual = sha1_of(&data[..]);
+        if actual != locked.sha1 {
+            return HashMismatch{entry, expected: locked.sha1, actual}.fail();
+        }
+
+        let target = cache_target(&root, &locked.path)?;
+        info!(log, "importing bundled file"; "target" => target.display().to_string());
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).context(Writing{path: parent.display().to_string()})?;
+        }
+        std::fs::write(&target, &data[..]).context(Writing{path: target.display().to_string()})?;
+    }
+
+    Ok(serde_json::from_reader(archive.by_name(CONFIG_ENTRY).context(Zip{entry: CONFIG_ENTRY})?)
+        .context(Json{entry: CONFIG_ENTRY})?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_bundled_files_inside_the_cache() {
+        let root = Path::new("cache_root");
+        assert_eq!(cache_target(root, "maven_cache/net/minecraftforge/forge.jar").unwrap(), root.join("maven_cache").join("net").join("minecraftforge").join("forge.jar"));
+        for path in &["../evil.jar", "maven_cache/../../evil.jar", "/etc/passwd", "maven_cache//forge.jar", "./forge.jar", "", "C:\\evil.jar", "a\\..\\..\\evil.jar"] {
+            assert!(cache_target(root, path).is_err(), "{}", path);
+        }
+    }
+}
@@ -52,6 +52,16 @@ pub mod error{
         NoFilesInFolder{
             path: String,
         },
+        #[snafu(display("Error while verifying {}: {}", item, source))]
+        Verifying{
+            item: String,
+            #[snafu(source(from(crate::maven::Error, Box::new)))]
+            source: Box<crate::maven::Error>,
+        },
+        #[snafu(display("No repository to fetch {} from", item))]
+        NoRepository{
+            item: String,
+        },
         #[snafu(display("{}", source))]
         Dynamic{
             source: Box<dyn std::error::Error + Send + Sync>
@@ -81,6 +91,9 @@ pub trait Cacheable: Send + Sized + 'static {
     type Cache: Cache<Self>;
     fn cached_path(&self) -> PathBuf;
     fn uri(&self) -> Result<Uri>;
+    fn is_cached(&self) -> bool {
+        <Self::Cache as Cache<Self>>::is_cached(self)
+    }
     fn reader(self, manager: download::Manager, log: Logger) -> BoxFuture<tokio::fs::File> {
         Box::pin(async move{
             let path = Self::Cache::with(self, manager, log).await?;
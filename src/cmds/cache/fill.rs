@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use snafu::Snafu;
+use indicatif::{ProgressBar,ProgressStyle};
+use crate::{
+    cache::Cache as _,
+    download,
+    maven,
+    mc_libs,
+    mod_source::ModpackConfig,
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "fill", about = "Prefetches everything the provided pack needs into the local cache.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to cache
+    pack_file: PathBuf,
+}
+
+#[derive(Debug,Snafu)]
+enum Error{
+    #[snafu(display("Error while caching forge (version: {}): {}", version, source))]
+    CachingForge{
+        version: String,
+        source: crate::cache::Error,
+    },
+    #[snafu(display("Error while caching forge's libraries: {}", source))]
+    CachingLibraries{
+        source: mc_libs::Error,
+    },
+}
+
+pub async fn fill(args: Args, log: slog::Logger) -> Result<(),crate::Error>
+{
+    let Args{pack_file} = args;
+
+    let pack = ModpackConfig::load_maybe_indirected(&pack_file).await?;
+    let manager = download::Manager::new();
+
+    let progress = ProgressBar::new(pack.mods.len() as u64 + 2);
+    progress.set_style(ProgressStyle::default_bar()
+        .template("[{elapsed_precise}] {wide_bar} {pos:>3}/{len:3} {msg:!}"));
+
+    let forge = pack.forge_maven_artifact();
+    progress.set_message(&format!("forge {}", pack.forge));
+    maven::Cache::with(forge.clone(), manager.clone(), log.clone()).await.context(CachingForge{version: pack.forge.clone()}).erased()?;
+    progress.inc(1);
+
+    progress.set_message("forge and minecraft libraries");
+    mc_libs::cache_libraries(forge, manager.clone(), log.clone()).await.context(CachingLibraries).erased()?;
+    progress.inc(1);
+
+    for modd in pack.mods.into_iter().map(|entry| entry.source) {
+        progress.set_message(&modd.identifier_string());
+        if !modd.is_cached() {
+            modd.cache(manager.clone(), log.clone()).await.context(error::Download)?;
+        }
+        progress.inc(1);
+    }
+    progress.finish_with_message("Done");
+    Ok(())
+}
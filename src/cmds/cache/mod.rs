@@ -0,0 +1,22 @@
+mod fill;
+pub use fill::fill;
+
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "cache", about = "Commands for managing the local download cache.")]
+pub enum Args{
+    #[structopt(name="fill")]
+    Fill(fill::Args),
+}
+
+impl Args{
+    pub async fn dispatch(self, log: slog::Logger) -> crate::Result<()>
+    {
+        match self{
+            Args::Fill(fill_args) => {
+                fill(fill_args, log).await
+            }
+        }
+    }
+}
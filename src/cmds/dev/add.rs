@@ -2,60 +2,82 @@ use std::path::PathBuf;
 use structopt::StructOpt;
 use crate::error::prelude::*;
 use snafu::Snafu;
-use crate::async_json;
+use crate::{
+    curseforge::{self, CurseLink, ProjectRef, ReleaseStatus},
+    download::HttpSimple,
+};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "add", about = "Adds a mod to the provided pack file.")]
 pub struct Args{
     /// The metadata json file for the pack you wish to modify
     pack_file: PathBuf,
-    /// The url for the mod you wish to add
+    /// The url for the mod file you wish to add, or a curseforge project slug or url to add its newest compatible file
     mod_url: String,
 }
 
 #[derive(Debug,Snafu)]
 enum Error{
-    #[snafu(display("pack {} does not exist",pack_file))]
-    MissingPack{
-        pack_file: String,
-        source: std::io::Error,
-    },
-    #[snafu(display("pack file {} is missing or in bad format", pack_file))]
-    BadPackfile{
-        pack_file: String,
-        source: async_json::Error,
-    },
-    #[snafu(display("error while creating new packfile: {}", pack_file))]
-    CreatingPack{
-        pack_file: String,
-        source: std::io::Error,
-    },
-    #[snafu(display("error while writing packfile: {}", pack_file))]
-    PackfileOutput{
-        pack_file: String,
-        source: async_json::Error,
-    },
     #[snafu(display("Unparseable modsource url: {} ({})", url, source))]
     UnparseableModsourceUrl{
         url: String,
         source: crate::Error,
     },
+    #[snafu(display("cdn links like {} don't say which project the file is from, use a curseforge file link instead", file_name))]
+    CdnLink{
+        file_name: String,
+    },
+    #[snafu(display("{} has no {} or more stable files for minecraft {}", id, release_status.value(), version))]
+    NoCompatibleFile{
+        id: String,
+        version: semver::VersionReq,
+        release_status: ReleaseStatus,
+    },
 }
 
 pub async fn add(args: Args) -> Result<(),crate::Error>
 {
-    use crate::mod_source::ModpackConfig;
+    use crate::mod_source::VersionBump;
 
     let Args{pack_file, mod_url} = args;
 
-    let res: Result<_,Error> = try{
-        let mut file = tokio::fs::File::open(pack_file.clone()).await.context(MissingPack{pack_file: pack_file.display().to_string()})?;
-        let mut pack: ModpackConfig = crate::async_json::read(&mut file).await.context(BadPackfile{pack_file: pack_file.display().to_string()})?;
-
-        pack.add_mod_by_url(mod_url.as_str()).context(UnparseableModsourceUrl{url: mod_url})?;
-
-        let mut out_file = tokio::fs::File::create(pack_file.clone()).await.context(CreatingPack{pack_file: pack_file.display().to_string()})?;
-        async_json::write_pretty( &mut out_file, &pack).await.context(PackfileOutput{pack_file: pack_file.display().to_string()})?;
+    let mut pack = super::load_for_editing(&pack_file).await?;
+    let http_client = HttpSimple::new();
+    let link = match mod_url.parse::<CurseLink>() {
+        Ok(link) => link,
+        //anything that isn't a link is taken as a project slug
+        Err(_) if !mod_url.contains('/') => CurseLink::Project(ProjectRef::Slug(mod_url.clone())),
+        Err(e) => return Err(UnparseableModsourceUrl{url: mod_url.clone()}.into_error(e)).erased(),
+    };
+    let changes = match link {
+        CurseLink::File{project, file_id} => {
+            let id = curseforge::api::resolve_project(project, http_client).await?;
+            pack.replace_mod(curseforge::Mod{ id, version: file_id }.into())
+        }
+        CurseLink::Project(project) => {
+            //not a link to a file, so find the newest one that fits the pack
+            let id = curseforge::api::resolve_project(project, http_client.clone()).await?;
+            let pack_status = pack.auto_update_release_status.unwrap_or(ReleaseStatus::Release);
+            //a mod already in the pack keeps its own settings
+            let existing = pack.find(&id).cloned();
+            let release_status = existing.as_ref().map_or(pack_status, |entry| entry.upgrade_release_status(pack_status));
+            let found = curseforge::api::find_most_recent(
+                curseforge::Mod{ id: id.clone(), version: 0 },
+                pack.version.clone(),
+                http_client,
+                release_status,
+                existing.and_then(|entry| entry.constraint),
+            ).await?;
+            match found {
+                Some(found) => pack.replace_mod(found.modd.into()),
+                None => return NoCompatibleFile{id, version: pack.version.clone(), release_status}.fail().erased(),
+            }
+        }
+        CurseLink::CdnFile{file_name, ..} => return CdnLink{file_name}.fail().erased(),
     };
-    res.erased()
+    for change in &changes {
+        println!("{}", change);
+    }
+    pack.bump_version_if_versioned(VersionBump::Patch, changes.iter().map(ToString::to_string).collect());
+    pack.save(&pack_file).await
 }
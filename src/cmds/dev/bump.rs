@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::mod_source::VersionBump;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "bump", about = "Bumps the version of the provided pack file, recording a changelog entry.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to modify
+    pack_file: PathBuf,
+    /// Which part of the version to bump: major, minor or patch
+    kind: VersionBump,
+    /// A line for the changelog, may be given more than once
+    #[structopt(short = "m", long = "message")]
+    messages: Vec<String>,
+}
+
+pub async fn bump(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, kind, messages} = args;
+
+    let mut pack = super::load_for_editing(&pack_file).await?;
+    let old_version = pack.pack_version.clone();
+    let new_version = pack.bump_version(kind, messages);
+    match old_version {
+        Some(old_version) => println!("Bumped {} from {} to {}", pack.name, old_version, new_version),
+        None => println!("{} is now at version {}", pack.name, new_version),
+    }
+    pack.save(&pack_file).await
+}
@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::{
+    download,
+    mod_source::ModpackConfig,
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "bundle", about = "Bundles the provided pack file along with every file it needs, for installing without network access.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to bundle
+    pack_file: PathBuf,
+    /// The path to create the bundle at
+    bundle_path: PathBuf,
+}
+
+pub async fn bundle(args: Args, log: slog::Logger) -> Result<(),crate::Error>
+{
+    let Args{pack_file, bundle_path} = args;
+
+    let pack = ModpackConfig::load_maybe_indirected(&pack_file).await?;
+    crate::bundle::write_bundle(pack, bundle_path, download::Manager::new(), log).await.erased()
+}
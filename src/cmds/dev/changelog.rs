@@ -0,0 +1,150 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    path::PathBuf,
+    str::FromStr,
+};
+use structopt::StructOpt;
+use crate::{
+    download::HttpSimple,
+    mod_source::{ModpackConfig, ModSource},
+    pack_diff::PackDiff,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "text" | "txt" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            s => Err(format!("Unknown format `{}`, expected markdown, text or json", s)),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "changelog", about = "Describes what changed between two versions of a pack.")]
+pub struct Args{
+    /// The metadata json file for the old version of the pack
+    old_pack: PathBuf,
+    /// The metadata json file for the new version of the pack
+    new_pack: PathBuf,
+    /// The output format: markdown, text or json
+    #[structopt(long = "format", default_value = "markdown")]
+    format: Format,
+    /// Don't fetch the changelogs of updated mods from curseforge
+    #[structopt(long = "no-fetch")]
+    no_fetch: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct Changelog {
+    name: String,
+    #[serde(flatten)]
+    diff: PackDiff,
+    ///Changelogs of updated mods' new files, by mod key
+    mod_changelogs: BTreeMap<String, String>,
+}
+
+fn describe(modd: &ModSource) -> String {
+    format!("{} ({})", modd.identifier_string(), modd.version_string())
+}
+
+fn version_heading(changelog: &Changelog) -> String {
+    match changelog.diff.pack_version {
+        Some(ref change) => match (&change.old, &change.new) {
+            (Some(old), Some(new)) => format!("{} {} → {}", changelog.name, old, new),
+            (None, Some(new)) => format!("{} {}", changelog.name, new),
+            _ => changelog.name.clone(),
+        },
+        None => changelog.name.clone(),
+    }
+}
+
+fn render(changelog: &Changelog, format: Format) -> String {
+    let (heading, section, item, quote) = match format {
+        Format::Markdown => ("## ", "### ", "- ", "  > "),
+        Format::Text => ("", "", "  * ", "      "),
+        Format::Json => return format!("{}\n", serde_json::to_string_pretty(changelog).expect("changelog is always serializable")),
+    };
+    let bold = |s: &str| if format == Format::Markdown { format!("**{}**", s) } else { s.to_owned() };
+
+    let mut out = String::new();
+    writeln!(out, "{}{}", heading, version_heading(changelog)).unwrap();
+    let diff = &changelog.diff;
+    if diff.is_empty() {
+        writeln!(out, "\nNo changes.").unwrap();
+        return out;
+    }
+    if let Some(ref forge) = diff.forge {
+        writeln!(out, "\n{} {} → {}", bold("Forge:"), forge.old, forge.new).unwrap();
+    }
+    if let Some(ref status) = diff.release_status {
+        let status_name = |status: Option<crate::curseforge::ReleaseStatus>| status.map(|status| status.value()).unwrap_or("disabled");
+        writeln!(out, "\n{} {} → {}", bold("Auto update release status:"), status_name(status.old), status_name(status.new)).unwrap();
+    }
+    if !diff.added.is_empty() {
+        writeln!(out, "\n{}Added", section).unwrap();
+        for modd in &diff.added {
+            writeln!(out, "{}{}", item, describe(modd)).unwrap();
+        }
+    }
+    if !diff.removed.is_empty() {
+        writeln!(out, "\n{}Removed", section).unwrap();
+        for modd in &diff.removed {
+            writeln!(out, "{}{}", item, describe(modd)).unwrap();
+        }
+    }
+    if !diff.updated.is_empty() {
+        writeln!(out, "\n{}Updated", section).unwrap();
+        for update in &diff.updated {
+            writeln!(out, "{}{}: {} → {}", item, update.new.identifier_string(), update.old.version_string(), update.new.version_string()).unwrap();
+            if let Some(mod_changelog) = changelog.mod_changelogs.get(&update.key) {
+                for line in mod_changelog.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                    writeln!(out, "{}{}", quote, line).unwrap();
+                }
+            }
+        }
+    }
+    out
+}
+
+pub async fn changelog(args: Args) -> Result<(),crate::Error>
+{
+    let Args{old_pack, new_pack, format, no_fetch} = args;
+
+    let old = ModpackConfig::load(&old_pack).await?;
+    let new = ModpackConfig::load(&new_pack).await?;
+    let diff = PackDiff::between(&old, &new);
+
+    let mut mod_changelogs = BTreeMap::new();
+    if !no_fetch {
+        let http_client = HttpSimple::new();
+        for update in &diff.updated {
+            if let ModSource::CurseforgeMod(ref curse_mod) = update.new.source {
+                match crate::curseforge::api::file_changelog(curse_mod.clone(), http_client.clone()).await {
+                    Ok(mod_changelog) => {
+                        mod_changelogs.insert(update.key.clone(), mod_changelog);
+                    }
+                    Err(e) => eprintln!("Couldn't fetch the changelog for {}: {}", curse_mod.id, e),
+                }
+            }
+        }
+    }
+
+    let changelog = Changelog{
+        name: new.name,
+        diff,
+        mod_changelogs,
+    };
+    print!("{}", render(&changelog, format));
+    Ok(())
+}
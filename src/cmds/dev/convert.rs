@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::pack_format::PackFormat;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "convert", about = "Converts a pack file between json, toml and yaml, going by the file extensions.")]
+pub struct Args{
+    /// The pack file to convert
+    pack_file: PathBuf,
+    /// Where to write the converted pack, its extension picks the format
+    output: PathBuf,
+}
+
+pub async fn convert(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, output} = args;
+
+    let pack = super::load_for_editing(&pack_file).await?;
+    pack.save(&output).await?;
+    println!("Converted {} ({}) to {} ({})",
+        pack_file.display(), PackFormat::for_path(&pack_file).extension(),
+        output.display(), PackFormat::for_path(&output).extension());
+    Ok(())
+}
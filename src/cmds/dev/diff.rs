@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::{
+    mod_source::ModpackConfig,
+    pack_diff::PackDiff,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "diff", about = "Shows the mods and settings that differ between two pack files.")]
+pub struct Args{
+    /// The metadata json file for the old version of the pack
+    old_pack: PathBuf,
+    /// The metadata json file for the new version of the pack
+    new_pack: PathBuf,
+    /// Print the differences as json
+    #[structopt(long = "json")]
+    json: bool,
+}
+
+pub async fn diff(args: Args) -> Result<(),crate::Error>
+{
+    let Args{old_pack, new_pack, json} = args;
+
+    let old = ModpackConfig::load(&old_pack).await?;
+    let new = ModpackConfig::load(&new_pack).await?;
+    let diff = PackDiff::between(&old, &new);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff).expect("diffs are always serializable"));
+        return Ok(());
+    }
+    if diff.is_empty() {
+        println!("No differences.");
+    }
+    if let Some(ref version) = diff.pack_version {
+        let describe = |version: &Option<semver::Version>| version.as_ref().map(ToString::to_string).unwrap_or_else(|| "unversioned".into());
+        println!("pack version: {} -> {}", describe(&version.old), describe(&version.new));
+    }
+    if let Some(ref forge) = diff.forge {
+        println!("forge: {} -> {}", forge.old, forge.new);
+    }
+    if let Some(ref status) = diff.release_status {
+        println!("auto_update_release_status: {:?} -> {:?}", status.old, status.new);
+    }
+    for modd in &diff.removed {
+        println!("- {}", modd.describe());
+    }
+    for modd in &diff.added {
+        println!("+ {}", modd.describe());
+    }
+    for update in &diff.updated {
+        println!("~ {}: {} -> {}", update.key, update.old.version_string(), update.new.version_string());
+    }
+    Ok(())
+}
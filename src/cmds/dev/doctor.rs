@@ -0,0 +1,136 @@
+use futures::prelude::*;
+use http::Uri;
+use std::{
+    path::PathBuf,
+    str::FromStr,
+};
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    curseforge,
+    download::{self, HttpSimple},
+    maven::RepositoryConfig,
+    mod_source::{ModEntry, ModSource, VersionBump},
+    error::prelude::*,
+};
+
+///How many mods are checked at once
+const CONCURRENT_CHECKS: usize = 16;
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("{} mods no longer resolve", count))]
+    Problems{
+        count: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Diagnosis{
+    Ok,
+    ///The curseforge project now lives at a different slug
+    Moved{
+        to: String,
+    },
+    Deleted,
+    Archived,
+    Unresolvable{
+        reason: String,
+    },
+}
+
+async fn diagnose(entry: ModEntry, http_client: HttpSimple, repositories: RepositoryConfig, manager: download::Manager) -> (ModEntry, Diagnosis) {
+    let diagnosis = match entry.source.clone() {
+        ModSource::CurseforgeMod(curse_mod) => match curseforge::api::current_slug(curse_mod.id.clone(), http_client).await {
+            Ok(ref id) if *id == curse_mod.id => Diagnosis::Ok,
+            Ok(id) => Diagnosis::Moved{ to: id },
+            Err(crate::Error::ProjectDeleted{..}) => Diagnosis::Deleted,
+            Err(crate::Error::ProjectArchived{..}) => Diagnosis::Archived,
+            Err(e) => Diagnosis::Unresolvable{ reason: e.to_string() },
+        },
+        ModSource::MavenMod{repo, artifact, ..} => match Uri::from_str(&repo) {
+            Ok(repo_uri) => {
+                let repos = repositories.repos_for(&artifact.group, Some(&repo_uri));
+                match artifact.resolve_version(repos, manager).await {
+                    Ok(_) => Diagnosis::Ok,
+                    Err(e) => Diagnosis::Unresolvable{ reason: e.to_string() },
+                }
+            }
+            Err(e) => Diagnosis::Unresolvable{ reason: e.to_string() },
+        },
+    };
+    (entry, diagnosis)
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "doctor", about = "Checks that every mod in the pack still resolves, flagging missing or moved projects.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to check
+    pack_file: PathBuf,
+    /// Rewrite the slugs of curseforge projects which have moved
+    #[structopt(long = "fix")]
+    fix: bool,
+}
+
+pub async fn doctor(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, fix} = args;
+
+    let mut pack = super::load_for_editing(&pack_file).await?;
+    let http_client = HttpSimple::new();
+    let repositories = pack.repository_config();
+    let manager = download::Manager::new().with_repositories(repositories.clone());
+
+    let mut diagnoses: Vec<(ModEntry, Diagnosis)> = futures::stream::iter(pack.mods.clone())
+        .map(move |entry| diagnose(entry, http_client.clone(), repositories.clone(), manager.clone()))
+        .buffer_unordered(CONCURRENT_CHECKS)
+        .collect()
+        .await;
+    diagnoses.sort_by_key(|(entry, _)| entry.key());
+
+    let total = diagnoses.len();
+    let mut problems = 0;
+    let mut changes = vec![];
+    for (entry, diagnosis) in diagnoses {
+        let key = entry.key();
+        match diagnosis {
+            Diagnosis::Ok => {}
+            Diagnosis::Moved{to} => {
+                println!("MOVED: {} is now {}", key, to);
+                match entry.source {
+                    ModSource::CurseforgeMod(ref curse_mod) if fix => {
+                        let renamed = ModSource::CurseforgeMod(curseforge::Mod{ id: to, ..curse_mod.clone() });
+                        let change = pack.rename_mod(&entry.identifier_string(), renamed).erased()?;
+                        println!("\t{}", change);
+                        changes.push(change.to_string());
+                    }
+                    _ => problems += 1,
+                }
+            }
+            Diagnosis::Deleted => {
+                println!("MISSING: {} no longer exists on curseforge", key);
+                problems += 1;
+            }
+            Diagnosis::Archived => {
+                println!("ARCHIVED: {} has been archived by its authors", key);
+                problems += 1;
+            }
+            Diagnosis::Unresolvable{reason} => {
+                println!("UNRESOLVABLE: {}: {}", key, reason);
+                problems += 1;
+            }
+        }
+    }
+
+    if !changes.is_empty() {
+        pack.bump_version_if_versioned(VersionBump::Patch, changes);
+        pack.save(&pack_file).await?;
+    }
+
+    if problems == 0 {
+        println!("All {} mods resolve", total);
+        Ok(())
+    } else {
+        Problems{count: problems}.fail().erased()
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::{
+    mod_source::ModpackConfig,
+    pack_format::PackFormat,
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "flatten", about = "Writes out a pack which extends another as one complete pack, with every base pack merged in.")]
+pub struct Args{
+    /// The pack file to flatten
+    pack_file: PathBuf,
+    /// Where to write the flattened pack, its extension picks the format. Printed if not given.
+    output: Option<PathBuf>,
+}
+
+pub async fn flatten(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, output} = args;
+
+    let pack = ModpackConfig::load_maybe_indirected(&pack_file).await?;
+    match output {
+        Some(output) => pack.save(&output).await,
+        None => {
+            println!("{}", PackFormat::for_path(&pack_file).to_string(&pack).erased()?);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::{
+    download::HttpSimple,
+    maven,
+    mod_source::{ModpackConfig, ModSource},
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "list", about = "Lists the mods in the provided pack file.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to list
+    pack_file: PathBuf,
+    /// Print the list as json
+    #[structopt(long = "json")]
+    json: bool,
+    /// Don't look up release statuses on curseforge
+    #[structopt(long = "no-fetch")]
+    no_fetch: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct Row {
+    source: &'static str,
+    id: String,
+    version: String,
+    release_status: Option<String>,
+    side: &'static str,
+    project_url: Option<String>,
+}
+
+impl Row {
+    fn cells(&self) -> [String; 6] {
+        [
+            self.source.to_owned(),
+            self.id.clone(),
+            self.version.clone(),
+            self.release_status.clone().unwrap_or_else(|| "?".into()),
+            self.side.to_owned(),
+            self.project_url.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+const HEADERS: [&str; 6] = ["SOURCE", "ID", "VERSION", "STATUS", "SIDE", "PROJECT URL"];
+
+fn print_table(rows: &[Row]) {
+    let cells: Vec<[String; 6]> = rows.iter().map(Row::cells).collect();
+    let mut widths: Vec<usize> = HEADERS.iter().map(|header| header.len()).collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |row: &[&str]| {
+        let line: Vec<String> = row.iter().zip(widths.iter()).map(|(cell, width)| format!("{:width$}", cell, width = *width)).collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+    print_row(&HEADERS);
+    for row in &cells {
+        print_row(&row.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+}
+
+pub async fn list(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, json, no_fetch} = args;
+
+    let pack = ModpackConfig::load(&pack_file).await?;
+    let http_client = HttpSimple::new();
+
+    let mut rows = vec![];
+    for entry in &pack.mods {
+        let (source, release_status) = match entry.source {
+            ModSource::CurseforgeMod(ref curse_mod) => {
+                let release_status = if no_fetch {
+                    None
+                } else {
+                    match crate::curseforge::api::file_release_status(curse_mod.clone(), http_client.clone()).await {
+                        Ok(status) => status.map(|status| status.value().to_owned()),
+                        Err(e) => {
+                            eprintln!("Couldn't look up the release status of {}: {}", curse_mod.id, e);
+                            None
+                        }
+                    }
+                };
+                ("curseforge", release_status)
+            }
+            ModSource::MavenMod { .. } => {
+                let release_status = if maven::version::is_snapshot(&entry.version_string()) { "Snapshot" } else { "Release" };
+                ("maven", Some(release_status.to_owned()))
+            }
+        };
+        rows.push(Row{
+            source,
+            id: entry.identifier_string(),
+            version: entry.version_string(),
+            release_status,
+            side: entry.side.value(),
+            project_url: entry.guess_project_url(),
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows).expect("rows are always serializable"));
+    } else {
+        print_table(&rows);
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    mod_source::ModpackConfig,
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "merge", about = "Merges two edited copies of a pack file, mod by mod. Usable as a git merge driver (`dev merge %O %A %B`).")]
+pub struct Args{
+    /// The common ancestor of both copies
+    base: PathBuf,
+    /// Our copy, which the result is written over unless --output is given
+    ours: PathBuf,
+    /// Their copy
+    theirs: PathBuf,
+    /// Where to write the merged pack
+    #[structopt(short = "o", long = "output")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug,Snafu)]
+enum Error{
+    #[snafu(display("{} conflicts need resolving by hand (our side was kept)", count))]
+    Conflicts{
+        count: usize,
+    },
+}
+
+pub async fn merge(args: Args) -> Result<(),crate::Error>
+{
+    let Args{base, ours, theirs, output} = args;
+
+    let base_pack = ModpackConfig::load(&base).await?;
+    let our_pack = ModpackConfig::load(&ours).await?;
+    let their_pack = ModpackConfig::load(&theirs).await?;
+
+    let result = crate::pack_merge::merge(&base_pack, &our_pack, &their_pack);
+    result.merged.save(output.as_ref().unwrap_or(&ours)).await?;
+
+    if result.conflicts.is_empty() {
+        Ok(())
+    } else {
+        for conflict in &result.conflicts {
+            eprintln!("CONFLICT {}", conflict);
+        }
+        Conflicts{count: result.conflicts.len()}.fail().erased()
+    }
+}
@@ -4,8 +4,39 @@ mod add;
 pub use add::add;
 mod package;
 pub use package::package;
+mod bundle;
+pub use bundle::bundle;
+mod publish;
+pub use publish::publish;
+mod bump;
+pub use bump::bump;
+mod changelog;
+pub use changelog::changelog;
+mod diff;
+pub use diff::diff;
+mod merge;
+pub use merge::merge;
+mod remove;
+pub use remove::remove;
+mod list;
+pub use list::list;
+mod search;
+pub use search::search;
+mod watch_upgrade;
+pub use watch_upgrade::watch_upgrade;
+mod doctor;
+pub use doctor::doctor;
+mod validate;
+pub use validate::validate;
+mod schema;
+pub use schema::schema;
+mod convert;
+pub use convert::convert;
+mod flatten;
+pub use flatten::flatten;
 
 use structopt::StructOpt;
+use super::Outcome;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "dev", about = "Commands for modpack developers.")]
@@ -16,10 +47,40 @@ pub enum Args{
     Upgrade(upgrade::Args),
     #[structopt(name="package")]
     Package(package::Args),
+    #[structopt(name="bundle")]
+    Bundle(bundle::Args),
+    #[structopt(name="publish")]
+    Publish(publish::Args),
+    #[structopt(name="bump")]
+    Bump(bump::Args),
+    #[structopt(name="changelog")]
+    Changelog(changelog::Args),
+    #[structopt(name="diff")]
+    Diff(diff::Args),
+    #[structopt(name="merge")]
+    Merge(merge::Args),
+    #[structopt(name="remove")]
+    Remove(remove::Args),
+    #[structopt(name="list")]
+    List(list::Args),
+    #[structopt(name="search")]
+    Search(search::Args),
+    #[structopt(name="watch-upgrade")]
+    WatchUpgrade(watch_upgrade::Args),
+    #[structopt(name="doctor")]
+    Doctor(doctor::Args),
+    #[structopt(name="validate")]
+    Validate(validate::Args),
+    #[structopt(name="schema")]
+    Schema(schema::Args),
+    #[structopt(name="convert")]
+    Convert(convert::Args),
+    #[structopt(name="flatten")]
+    Flatten(flatten::Args),
 }
 
 impl Args{
-    pub async fn dispatch(self, _log: slog::Logger) -> crate::Result<()>
+    pub async fn dispatch(self, log: slog::Logger) -> crate::Result<Outcome>
     {
         match self{
             Args::Add(add_args) => {
@@ -31,6 +92,60 @@ impl Args{
             Args::Package(package_args) => {
                 package(package_args).await
             }
-        }
+            Args::Bundle(bundle_args) => {
+                bundle(bundle_args, log).await
+            }
+            Args::Publish(publish_args) => {
+                publish(publish_args).await
+            }
+            Args::Bump(bump_args) => {
+                bump(bump_args).await
+            }
+            Args::Changelog(changelog_args) => {
+                changelog(changelog_args).await
+            }
+            Args::Diff(diff_args) => {
+                diff(diff_args).await
+            }
+            Args::Merge(merge_args) => {
+                merge(merge_args).await
+            }
+            Args::Remove(remove_args) => {
+                remove(remove_args).await
+            }
+            Args::List(list_args) => {
+                list(list_args).await
+            }
+            Args::Search(search_args) => {
+                search(search_args).await
+            }
+            Args::WatchUpgrade(watch_upgrade_args) => {
+                return watch_upgrade(watch_upgrade_args).await;
+            }
+            Args::Doctor(doctor_args) => {
+                doctor(doctor_args).await
+            }
+            Args::Validate(validate_args) => {
+                validate(validate_args).await
+            }
+            Args::Schema(schema_args) => {
+                schema(schema_args).await
+            }
+            Args::Convert(convert_args) => {
+                convert(convert_args).await
+            }
+            Args::Flatten(flatten_args) => {
+                flatten(flatten_args).await
+            }
+        }?;
+        Ok(Outcome::Done)
+    }
+}
+///Loads a pack to edit and save back, warning about any duplicate mods normalizing drops from it
+async fn load_for_editing(pack_file: &std::path::Path) -> crate::Result<crate::mod_source::ModpackConfig> {
+    let (pack, dropped) = crate::mod_source::ModpackConfig::load_normalizing(pack_file).await?;
+    for change in dropped {
+        eprintln!("warning: dropped a duplicate entry from {}: {}", pack.name, change);
     }
-}
\ No newline at end of file
+    Ok(pack)
+}
@@ -1,4 +1,7 @@
-use crate::error::prelude::*;
+use crate::{
+    pack_format::PackFormat,
+    error::prelude::*,
+};
 use std::{
     path::PathBuf,
     io::{Write,Seek},
@@ -73,8 +76,8 @@ pub async fn package(args: Args) -> Result<(),crate::Error>
         {
             let res: Result<_,HybridPackfileError> = try{
                 let mut writer = zip::write::ZipWriter::new(&mut tmp);
-                //with the relevant file inside as "config.json"
-                writer.start_file("config.json",zip::write::FileOptions::default()).context(Zip)?;
+                //with the relevant file inside as "config.json" (or .toml or .yaml, whichever the pack is written in)
+                writer.start_file(PackFormat::for_path(&pack_file).hybrid_entry(),zip::write::FileOptions::default()).context(Zip)?;
                 writer.write_all(&pack_config_contents[..]).context(Io)?;
                 writer.finish().context(Zip)?;
             };
@@ -0,0 +1,132 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+};
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    maven::{
+        Artifact,
+        publish::{Credentials, Publisher},
+    },
+    mod_source::ModpackConfig,
+    pack_format::PackFormat,
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "publish", about = "Publishes the provided pack file to a maven repository.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to publish
+    pack_file: PathBuf,
+    /// The repository to publish to, either a http(s) url or a local directory
+    repo: String,
+    /// The maven group to publish the pack under
+    #[structopt(long = "group")]
+    group: String,
+    /// The maven artifact id to publish the pack as (defaults to the pack's folder name)
+    #[structopt(long = "artifact")]
+    artifact: Option<String>,
+    /// The version to publish the pack as (defaults to the pack's own version)
+    #[structopt(long = "version")]
+    version: Option<String>,
+    /// A one-click installer created by `dev package`, to publish alongside the pack
+    #[structopt(long = "installer")]
+    installer: Option<PathBuf>,
+    /// Username for the repository. The password is read from MODPACK_TOOL_REPO_PASSWORD, or asked for
+    #[structopt(long = "username")]
+    username: Option<String>,
+}
+
+///Where the repository password is read from, so it stays out of shell history and process listings
+const PASSWORD_VAR: &str = "MODPACK_TOOL_REPO_PASSWORD";
+
+#[derive(Debug,Snafu)]
+enum Error{
+    #[snafu(display("file {} could not be read: {}", path, source))]
+    ReadingFile{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("pack file {} is in bad format: {}", pack_file, source))]
+    BadPackfile{
+        pack_file: String,
+        source: crate::pack_format::Error,
+    },
+    #[snafu(display("pack file {} has no pack_version, so --version must be given", pack_file))]
+    NoVersion{
+        pack_file: String,
+    },
+    #[snafu(display("no password for the repository, set {} or run from a terminal to be asked", var))]
+    NoPassword{
+        var: &'static str,
+    },
+    #[snafu(display("password could not be read: {}", source))]
+    ReadingPassword{
+        source: std::io::Error,
+    },
+    #[snafu(display("Error while publishing: {}", source))]
+    Publishing{
+        source: crate::maven::publish::Error,
+    },
+}
+
+fn read_password() -> Result<String, Error> {
+    if let Ok(password) = std::env::var(PASSWORD_VAR) {
+        return Ok(password);
+    }
+    if !crate::prompt::is_interactive() {
+        return NoPassword{var: PASSWORD_VAR}.fail();
+    }
+    print_inline!("Password: ");
+    console::Term::stdout().read_secure_line().context(ReadingPassword)
+}
+
+pub async fn publish(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, repo, group, artifact, version, installer, username} = args;
+
+    let res: Result<_,Error> = try{
+        let pack_contents = std::fs::read(&pack_file).context(ReadingFile{path: pack_file.display().to_string()})?;
+        let format = PackFormat::for_path(&pack_file);
+        let pack: ModpackConfig = format.parse(&String::from_utf8_lossy(&pack_contents)).context(BadPackfile{pack_file: pack_file.display().to_string()})?;
+
+        let credentials = match username {
+            Some(username) => Some(Credentials{
+                username,
+                password: read_password()?,
+            }),
+            None => None,
+        };
+        let version = match version.or_else(|| pack.pack_version.as_ref().map(ToString::to_string)) {
+            Some(version) => version,
+            None => NoVersion{pack_file: pack_file.display().to_string()}.fail()?,
+        };
+
+        let publisher = Publisher::new(&repo, credentials).context(Publishing)?;
+
+        let pack_artifact = Artifact{
+            group,
+            artifact: artifact.unwrap_or_else(|| pack.folder().to_lowercase()),
+            version,
+            classifier: None,
+            extension: Some(format.extension().into()),
+        };
+
+        println!("Publishing {}", pack_artifact.to_string());
+        publisher.publish_file(pack_artifact.clone(), pack_contents).await.context(Publishing)?;
+        if let Some(installer) = installer {
+            let installer_contents = std::fs::read(&installer).context(ReadingFile{path: installer.display().to_string()})?;
+            let installer_artifact = Artifact{
+                classifier: Some("installer".into()),
+                extension: Some(installer.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_else(|| "exe".into())),
+                ..pack_artifact.clone()
+            };
+            println!("Publishing {}", installer_artifact.to_string());
+            publisher.publish_file(installer_artifact, installer_contents).await.context(Publishing)?;
+        }
+        publisher.publish_pom(pack_artifact.clone(), pack.name.clone(), format.extension().into()).await.context(Publishing)?;
+        publisher.update_metadata(pack_artifact).await.context(Publishing)?;
+    };
+    res.erased()
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::{
+    download::HttpSimple,
+    mod_source::{ModSource, VersionBump},
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "remove", about = "Removes a mod from the provided pack file.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to modify
+    pack_file: PathBuf,
+    /// The mod to remove, as a curseforge id, maven group:artifact, or url
+    mod_id: String,
+}
+
+pub async fn remove(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, mod_id} = args;
+
+    let mut pack = super::load_for_editing(&pack_file).await?;
+    let change = pack.remove_mod(&mod_id).erased()?;
+
+    if let crate::pack_edit::PackChange::Removed(ref entry) = change {
+        if let ModSource::CurseforgeMod(ref curse_mod) = entry.source {
+            match crate::curseforge::api::required_by(curse_mod.id.clone(), HttpSimple::new()).await {
+                Ok(dependents) => {
+                    let remaining: Vec<_> = dependents.iter()
+                        .filter(|id| pack.find(id).is_some())
+                        .collect();
+                    for id in remaining {
+                        eprintln!("warning: {} requires {}", id, curse_mod.id);
+                    }
+                }
+                Err(e) => eprintln!("warning: couldn't check which mods depend on {}: {}", curse_mod.id, e),
+            }
+        }
+    }
+
+    println!("{}", change);
+    pack.bump_version_if_versioned(VersionBump::Patch, vec![change.to_string()]);
+    pack.save(&pack_file).await
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use crate::{
+    pack_validate,
+    error::prelude::*,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "schema", about = "Prints a JSON Schema for pack files, for editors to check and autocomplete them with.")]
+pub struct Args{
+    /// Write the schema to this file instead of printing it
+    #[structopt(short = "o", long = "output")]
+    output: Option<PathBuf>,
+}
+
+pub async fn schema(args: Args) -> Result<(),crate::Error>
+{
+    let schema = serde_json::to_string_pretty(&pack_validate::schema()).context(error::Json)?;
+    match args.output {
+        Some(path) => std::fs::write(&path, schema).context(error::Io),
+        None => {
+            println!("{}", schema);
+            Ok(())
+        }
+    }
+}
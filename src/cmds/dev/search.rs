@@ -0,0 +1,37 @@
+use structopt::StructOpt;
+use crate::download::HttpSimple;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "search", about = "Searches curseforge for mods.")]
+pub struct Args{
+    /// What to search for
+    query: Vec<String>,
+    /// Print the results as json
+    #[structopt(long = "json")]
+    json: bool,
+}
+
+pub async fn search(args: Args) -> Result<(),crate::Error>
+{
+    let Args{query, json} = args;
+
+    let results = crate::curseforge::api::search(query.join(" "), HttpSimple::new()).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results).expect("search results are always serializable"));
+        return Ok(());
+    }
+    if results.is_empty() {
+        println!("No mods found.");
+    }
+    for result in results {
+        let downloads = result.downloads.map(|downloads| format!("{} downloads", downloads)).unwrap_or_else(|| "unknown downloads".into());
+        println!("{} ({}) - {}", result.name, result.id, downloads);
+        if !result.game_versions.is_empty() {
+            println!("\tminecraft {}", result.game_versions.join(", "));
+        }
+        if !result.summary.is_empty() {
+            println!("\t{}", result.summary);
+        }
+    }
+    Ok(())
+}
@@ -12,41 +12,30 @@ use kuchiki::{
     NodeDataRef,
 };
 use crate::{
-    download::HttpSimple,
+    download::{self, HttpSimple},
     curseforge::ReleaseStatus,
+    maven::{self, version::{ComparableVersion, VersionRequest}},
     mod_source::ModList,
+    pack_format::PackFormat,
+    prompt::{prompt_yes_no, Response},
     error::{self,ResultExt as _},
 };
+use http::Uri;
 use std::{
+    collections::BTreeSet,
     io::Write,
+    path::Path,
+    str::FromStr,
     sync::Arc,
 };
-use url::Url;
+use indicatif::{MultiProgress, ProgressBar};
+use crate::cmds::update::bar_style;
 use structopt::StructOpt;
 use snafu::{Snafu,OptionExt,ResultExt};
 
 use termcolor::{ColorSpec, WriteColor, Color};
 
 
-macro_rules! print_inline{
-    ($($args:tt)+) => {{
-        print!($($args)+);
-        if let Err(e) = std::io::stdout().flush(){
-            panic!("Failed to flush stdout: {}",e);
-        }
-    }};
-}
-
-macro_rules! readln{
-    () => {{
-        let mut new_name = String::new();
-        if let Err(e) = std::io::stdin().read_line(&mut new_name){
-            panic!("Failed to read stdin: {}",e);
-        }
-        new_name
-    }};
-}
-
 macro_rules! format_coloredln{
     ($output:expr; $($rest:tt)+ ) => {
         let mut buf = $output.buffer();
@@ -109,109 +98,119 @@ lazy_static! {
     };
 }
 
-#[derive(Debug)]
-struct ModVersionInfo {
-    modd: curseforge::Mod,
-    download_url: Url,
-    release_status: ReleaseStatus,
-}
-
-#[derive(Debug,PartialEq, Eq)]
-enum Response {
-    Yes,
-    No,
-}
-
-impl Response{
-    fn from_str(s: &str) -> Result<Option<Self>, ()> {
-        use nom::bytes::complete::*;
-        use nom::combinator::*;
-        use nom::branch::*;
-
-        fn error<'a, I>() -> impl (Fn(nom::Err<(I,nom::error::ErrorKind)>) -> ()) + 'a{
-            move |_|{}
-        }
-
-        let (rest,out) = alt((
-            map(tag_no_case("yes"), |_| Some(Self::Yes)),
-            map(tag_no_case("y"), |_| Some(Self::Yes)),
-            map(tag_no_case("no"), |_| Some(Self::No)),
-            map(tag_no_case("n"), |_| Some(Self::No)),
-            map(tag(""), |_| None)
-        ))(s.trim()).map_err(error())?;
-        if rest.is_empty(){
-            Ok(out)
-        } else {
-            Err(())
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests{
-    use super::Response;
+    use super::{Options, Response, Selection, UpgradePolicy};
+    use crate::curseforge::ReleaseStatus;
     #[test]
-    fn response_parses_yes(){
-        assert_eq!(Response::from_str("yes"),Ok(Some(Response::Yes)));
-        assert_eq!(Response::from_str("Yes"),Ok(Some(Response::Yes)));
-        assert_eq!(Response::from_str("y"),Ok(Some(Response::Yes)));
-        assert_eq!(Response::from_str("Y"),Ok(Some(Response::Yes)));
+    fn selection_parses_lists(){
+        assert_eq!(Selection::from_str("", 5),Ok(Selection::All));
+        assert_eq!(Selection::from_str("All", 5),Ok(Selection::All));
+        assert_eq!(Selection::from_str("n", 5),Ok(Selection::Nothing));
+        assert_eq!(Selection::from_str("1, 3-5", 5),Ok(Selection::Picked(vec![0, 2, 3, 4].into_iter().collect())));
+        assert!(Selection::from_str("0", 5).is_err());
+        assert!(Selection::from_str("4-6", 5).is_err());
+        assert!(Selection::from_str("3-1", 5).is_err());
+        assert!(Selection::from_str("some", 5).is_err());
     }
 
     #[test]
-    fn response_parses_no(){
-        assert_eq!(Response::from_str("no"),Ok(Some(Response::No)));
-        assert_eq!(Response::from_str("No"),Ok(Some(Response::No)));
-        assert_eq!(Response::from_str("n"),Ok(Some(Response::No)));
-        assert_eq!(Response::from_str("N"),Ok(Some(Response::No)));
+    fn policy_only_narrows_release_status(){
+        let policy = UpgradePolicy{ max_release_status: Some(ReleaseStatus::Beta), ..UpgradePolicy::default() };
+        assert_eq!(policy.limit(ReleaseStatus::Alpha), ReleaseStatus::Beta);
+        assert_eq!(policy.limit(ReleaseStatus::Beta), ReleaseStatus::Beta);
+        assert_eq!(policy.limit(ReleaseStatus::Release), ReleaseStatus::Release);
+        assert_eq!(UpgradePolicy::default().limit(ReleaseStatus::Alpha), ReleaseStatus::Alpha);
     }
 
     #[test]
-    fn response_parses_other(){
-        assert!(Response::from_str("adsafggg").is_err());
-        assert!(Response::from_str("??£££23").is_err());
+    fn unattended_runs_keep_release_status_unless_the_policy_allows(){
+        let options = Options{ yes: true, ..Options::default() };
+        assert_eq!(options.confirm("Upgrade now?", Response::Yes), Response::Yes);
+        assert_eq!(options.confirm_status(ReleaseStatus::Beta), Response::No);
+        let options = Options{ policy: UpgradePolicy{ max_release_status: Some(ReleaseStatus::Beta), ..UpgradePolicy::default() }, ..options };
+        assert_eq!(options.confirm_status(ReleaseStatus::Beta), Response::Yes);
+        assert_eq!(options.confirm_status(ReleaseStatus::Alpha), Response::No);
     }
+}
 
-    #[test]
-    fn response_parses_empty(){
-        assert_eq!(Response::from_str(""),Ok(None));
-    }
+///Which of a numbered list of updates to take
+#[derive(Debug, PartialEq, Eq)]
+enum Selection {
+    All,
+    Nothing,
+    ///Zero based indices into the list
+    Picked(BTreeSet<usize>),
 }
 
-fn prompt_yes_no(prompt: &str, default: Response) -> Response {
-    loop{
-        match default {
-            Response::Yes => print_inline!("{}[Y/n]",prompt),
-            Response::No => print_inline!("{}[y/N]",prompt),
+impl Selection{
+    ///Parses `all`, `none`, or one based numbers and ranges like `1,3-5`, for a list of `count` items
+    fn from_str(s: &str, count: usize) -> Result<Self, ()> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "" | "a" | "all" => return Ok(Selection::All),
+            "n" | "none" => return Ok(Selection::Nothing),
+            _ => {}
         }
-        let line = readln!();
-        match Response::from_str(line.as_str()){
-            Ok(Some(r)) => return r,
-            Ok(None) => return default,
-            Err(_) => println!("Please enter yes, no, or nothing."),
+        let mut picked = BTreeSet::new();
+        for part in s.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+            let (start, end) = match part.find('-') {
+                Some(dash) => (&part[..dash], &part[dash + 1..]),
+                None => (part, part),
+            };
+            let start = usize::from_str(start).map_err(|_| ())?;
+            let end = usize::from_str(end).map_err(|_| ())?;
+            if start == 0 || start > end || end > count {
+                return Err(());
+            }
+            picked.extend(start - 1..end);
+        }
+        Ok(Selection::Picked(picked))
+    }
+
+    fn includes(&self, index: usize) -> bool {
+        match *self {
+            Selection::All => true,
+            Selection::Nothing => false,
+            Selection::Picked(ref picked) => picked.contains(&index),
         }
     }
 }
 
-//Checks if any curseforge projects have been moved, and updates the names
-fn update_project_names(mods: ModList) -> Vec<impl Future<Output=Result<ModSource,error::Error>> + Send + 'static>{
-    let http_client = HttpSimple::new();
-    mods.into_iter().map(|modd|{
-        let http_client = http_client.clone();
-        async move{
-            match modd {
-                ModSource::CurseforgeMod(cfm) => {
-                    let (_res,url) = http_client.get_following_redirects(cfm.project_uri().context(error::Uri)?).context(error::Download)?.await.context(error::Download)?;
-                    let id = crate::curseforge::parse_modid_from_url(url.as_str()).expect("Bad redirect on curseforge?");
-                    Ok(ModSource::CurseforgeMod(crate::curseforge::Mod{
-                        id,
-                        ..cfm
-                    }))
+///A mod after checking where its project lives now
+enum Followed{
+    ///The mod, with its slug updated if the project was renamed
+    Found(ModEntry),
+    ///The project was deleted or archived
+    Gone(ModEntry, Outcome),
+}
+
+//Checks if a curseforge project has been moved, and updates the name
+fn follow_project(entry: ModEntry, http_client: HttpSimple) -> impl Future<Output=Result<Followed,error::Error>> + Send + 'static {
+    async move{
+        let source = match entry.source.clone() {
+            ModSource::CurseforgeMod(cfm) => {
+                let id = match curseforge::api::current_slug(cfm.id.clone(), http_client).await {
+                    Ok(id) => id,
+                    Err(error::Error::ProjectDeleted{..}) => return Ok(Followed::Gone(entry, Outcome::Deleted)),
+                    Err(error::Error::ProjectArchived{..}) => return Ok(Followed::Gone(entry, Outcome::Archived)),
+                    Err(e) => return Err(e),
+                };
+                if id != cfm.id {
+                    println!("{} has been renamed to {}", cfm.id, id);
                 }
-                mvn @ ModSource::MavenMod{..} => Ok(mvn),
+                ModSource::CurseforgeMod(crate::curseforge::Mod{
+                    id,
+                    ..cfm
+                })
             }
-        }
-    }).collect()
+            mvn @ ModSource::MavenMod{..} => mvn,
+        };
+        Ok(Followed::Found(ModEntry{
+            source,
+            ..entry
+        }))
+    }
 }
 
 trait SelectExt{
@@ -237,57 +236,142 @@ impl SelectExt for NodeDataRef<ElementData>{
     }
 }
 
-fn find_most_recent(
-    curse_mod: curseforge::Mod,
-    target_game_version: semver::VersionReq,
-    http_client: HttpSimple,
-    target_release_status: ReleaseStatus,
-) -> impl Future<Output=Result<Option<ModVersionInfo>,crate::Error>> + Send {
-    let mut stream = Box::pin(crate::curseforge::api::all_for_version(curse_mod, http_client, target_game_version).try_filter(move |release_info| {
-        futures::future::ready(
-            target_release_status.accepts(release_info.release_status)
-                //already filtering by this on get
-                //&& game_versions.iter().any(|ver| target_game_version.matches(ver))
-        )
-    }));
-    async move{
-        Ok(if let Some(release_info) = stream.try_next().await?{
-            let url = Url::parse(&format!("https://www.curseforge.com/minecraft/mc-mods/{}/download/{}/file",release_info.modd.id,release_info.modd.version)).expect("bad prechecked url");
-            Some(ModVersionInfo {
-                modd: release_info.modd,
-                download_url: url,
-                release_status: release_info.release_status,
-            })
-        }else{
-            None
-        })
+use crate::curseforge::{self, api::{find_most_recent, ModVersionInfo, SearchResult}};
+use crate::mod_source::{ModEntry, ModSource, ModpackConfig, VersionBump};
+use crate::pack_edit::PackField;
+use crate::upgrade_report::{self, ModReport, Outcome, ReportFormat, UpgradeReport};
+
+///Which mods an upgrade may touch, and how unstable their new versions may be
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpgradePolicy{
+    ///The least stable release status an upgrade may pick, on top of the pack's own setting
+    pub max_release_status: Option<ReleaseStatus>,
+    ///Mods that are left exactly as they are, without being looked up
+    pub skip: Vec<String>,
+    ///Mods that are looked up and reported, but never updated
+    pub pin: Vec<String>,
+}
+
+impl UpgradePolicy{
+    fn skips(&self, modd: &ModSource) -> bool {
+        self.skip.iter().any(|identifier| modd.matches(identifier))
+    }
+
+    fn pins(&self, modd: &ModSource) -> bool {
+        self.pin.iter().any(|identifier| modd.matches(identifier))
+    }
+
+    ///Whether `max_release_status` explicitly lets the pack use mods of `status`
+    fn allows(&self, status: ReleaseStatus) -> bool {
+        self.max_release_status.map_or(false, |max| max.accepts(status))
+    }
+
+    ///Restricts `status` to at most `max_release_status`
+    fn limit(&self, status: ReleaseStatus) -> ReleaseStatus {
+        match self.max_release_status {
+            Some(max) if status.accepts(max) => max,
+            _ => status,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Options{
+    yes: bool,
+    dry_run: bool,
+    policy: UpgradePolicy,
+}
+
+impl Options{
+    fn interactive(&self) -> bool {
+        !self.yes && !self.dry_run
+    }
+
+    ///Asks `prompt`, or takes `default` when running unattended
+    fn confirm(&self, prompt: &str, default: Response) -> Response {
+        if self.interactive() {
+            prompt_yes_no(prompt, default)
+        } else {
+            println!("{} {}", prompt, if default == Response::Yes { "yes" } else { "no" });
+            default
+        }
+    }
+
+    ///Whether to let the pack use mods as unstable as `status`, which unattended runs only do when the policy allows it
+    fn confirm_status(&self, status: ReleaseStatus) -> Response {
+        let default = if self.policy.allows(status) { Response::Yes } else { Response::No };
+        self.confirm(&format!("This will mean your pack must use {} status mods. Is this ok?", status.value().to_lowercase()), default)
+    }
+
+    ///Asks which of a numbered list of updates to take
+    fn choose_updates(&self, count: usize) -> Selection {
+        if !self.interactive() {
+            return Selection::All;
+        }
+        loop{
+            print_inline!("Take which updates? [A]ll, [n]one, or numbers such as 1,3-5: ");
+            match Selection::from_str(readln!().as_str(), count){
+                Ok(selection) => return selection,
+                Err(_) => println!("Please enter all, none, or numbers between 1 and {}.", count),
+            }
+        }
     }
 }
 
-use crate::curseforge;
-use crate::mod_source::{ModSource, ModpackConfig};
+///How many alternatives to suggest for each incompatible mod
+const ALTERNATIVES: usize = 3;
+
+///Mods that work on `version` and come up when searching for the name of `curse_mod`
+async fn find_alternatives(curse_mod: curseforge::Mod, version: semver::VersionReq, http_client: HttpSimple) -> Vec<SearchResult> {
+    let query = curse_mod.id.replace('-', " ");
+    match curseforge::api::search(query, http_client).await {
+        Ok(results) => results.into_iter()
+            .filter(|result| result.id != curse_mod.id && result.supports(&version))
+            .take(ALTERNATIVES)
+            .collect(),
+        //alternatives are only suggestions, so a failed search just leaves them out
+        Err(_) => vec![],
+    }
+}
 
 fn new_version(
     target_game_version: semver::VersionReq,
     pack_path: String,
     mut pack: ModpackConfig,
-) -> impl Future<Output=Result<(), error::Error>> + Send + 'static {
+    options: Options,
+) -> impl Future<Output=Result<UpgradeReport, error::Error>> + Send + 'static {
     let http_client = HttpSimple::new();
-
-    let strm = update_project_names(pack.mods.clone()).into_iter().collect::<futures::stream::futures_unordered::FuturesUnordered<_>>()
-        .and_then(move |modd|{
+    let requested_game_version = target_game_version.clone();
+    let (skipped, checked): (ModList, ModList) = pack.mods.iter().cloned().partition(|entry| options.policy.skips(entry));
+    let policy = options.policy.clone();
+
+    let strm = checked.into_iter()
+        .map(|entry| follow_project(entry, http_client.clone()))
+        .collect::<futures::stream::futures_unordered::FuturesUnordered<_>>()
+        .and_then(move |followed: Followed|{
             let target_game_version = target_game_version.clone();
             let http_client_handle = http_client.clone();
+            let policy = policy.clone();
             async move{
-                match modd{
+                let entry = match followed {
+                    Followed::Found(entry) => entry,
+                    Followed::Gone(entry, outcome) => {
+                        format_coloredln!((*COLOR_OUTPUT); (&FAILURE_COLOR){"INCOMPATIBLE: "}, "{} ({})", entry.key(), outcome );
+                        let report = ModReport::new(&entry, None, outcome);
+                        return Ok((entry,None,report));
+                    }
+                };
+                let search_status = policy.limit(entry.upgrade_release_status(ReleaseStatus::Alpha));
+                match entry.source.clone(){
                     ModSource::CurseforgeMod(curse_mod) => {
                         let found = find_most_recent(curse_mod.clone(),
-                                            target_game_version,
-                                            http_client_handle,
-                                            ReleaseStatus::Alpha).await?;
+                                            target_game_version.clone(),
+                                            http_client_handle.clone(),
+                                            search_status,
+                                            entry.constraint.clone()).await?;
                         if let Some(found) = found {
                             format_colored!((*COLOR_OUTPUT); (&SUCCESS_COLOR){"  COMPATIBLE: "}, "{}", curse_mod.id );
-                            assert_eq!(curse_mod.id, found.modd.id);
                             if found.release_status != ReleaseStatus::Release {
                                 let a_an = if found.release_status == ReleaseStatus::Alpha{
                                     "an"
@@ -299,15 +383,19 @@ fn new_version(
                                 format_colored!((*COLOR_OUTPUT); (&INFO_COLOR){ " (as {} {} release)", a_an, found.release_status.value() } );
                             }
                             format_coloredln!((*COLOR_OUTPUT); "" );
-                            Ok((curse_mod.into(),Some(found.release_status)))
+                            let report = ModReport::new(&entry, Some(found.modd.version.to_string()), Outcome::Compatible(found.release_status));
+                            Ok((entry,Some(found),report))
                         } else {
                             format_coloredln!((*COLOR_OUTPUT); (&FAILURE_COLOR){"INCOMPATIBLE: "}, "{}", curse_mod.id );
-                            Ok((curse_mod.into(),None))
+                            let mut report = ModReport::new(&entry, None, Outcome::Incompatible);
+                            report.alternatives = find_alternatives(curse_mod, target_game_version, http_client_handle).await;
+                            Ok((entry,None,report))
                         }
                     }
-                    ModSource::MavenMod { artifact, repo } => {
-                        format_colored!((*COLOR_OUTPUT); (&WARN_COLOR){"you must check maven mod: {:?}",artifact});
-                        Ok((ModSource::MavenMod { artifact, repo },None))
+                    mvn @ ModSource::MavenMod { .. } => {
+                        format_colored!((*COLOR_OUTPUT); (&WARN_COLOR){"you must check maven mod: {}",mvn.identifier_string()});
+                        let report = ModReport::new(&entry, None, Outcome::Unchecked);
+                        Ok((entry,None,report))
                     },
                 }
             }
@@ -315,16 +403,21 @@ fn new_version(
 
     async move{
 
-        let modlist: Vec<(ModSource,Option<ReleaseStatus>)> = strm.try_collect::<Vec<_>>().await?;
+        let modlist: Vec<(ModEntry,Option<ModVersionInfo>,ModReport)> = strm.try_collect::<Vec<_>>().await?;
 
         let mut total = 0_usize;
         let mut alpha_compatible = 0_usize;
         let mut beta_compatible = 0_usize;
         let mut compatible = vec![];
+        let mut found_files = vec![];
         let mut incompatible = vec![];
+        let mut reports: Vec<ModReport> = skipped.iter().map(|entry| ModReport::new(entry, None, Outcome::Skipped)).collect();
 
-        for (modd,status) in modlist{
+        for (modd,found,report) in modlist{
             total += 1;
+            reports.push(report);
+            let status = found.as_ref().map(|found| found.release_status);
+            found_files.extend(found.map(|found| ModSource::CurseforgeMod(found.modd)));
             match status{
                 None => incompatible.push(modd),
                 Some(ReleaseStatus::Alpha) => {
@@ -340,6 +433,8 @@ fn new_version(
                 }
             }
         }
+        //skipped mods are carried over as they are
+        compatible.extend(skipped);
 
         if incompatible.is_empty() {
             let pack_update_status = pack.auto_update_release_status.unwrap_or(ReleaseStatus::Release);
@@ -355,38 +450,52 @@ fn new_version(
                 let percent_alpha_compatible = (alpha_compatible as f64)/(total as f64) * 100.0;
                 println!("(although {:.1}% are compatible only in alpha release)",percent_alpha_compatible);
             }
-            if prompt_yes_no("Upgrade now?",Response::Yes) == Response::Yes{
+            if options.confirm("Upgrade now?",Response::Yes) == Response::Yes{
                 match min_required_status {
                     ReleaseStatus::Alpha if pack_update_status != ReleaseStatus::Alpha => {
-                        if prompt_yes_no("This will mean your pack must use alpha status mods. Is this ok?",Response::No) == Response::No{
+                        if options.confirm_status(ReleaseStatus::Alpha) == Response::No{
                             println!("Canceling upgrade");
-                            return Ok(());
+                            return Ok(UpgradeReport::new(&pack, requested_game_version, options.dry_run, false, reports));
                         }
                     },
                     ReleaseStatus::Beta if pack_update_status != ReleaseStatus::Beta => {
-                        if prompt_yes_no("This will mean your pack must use beta status mods. Is this ok?",Response::No) == Response::No{
+                        if options.confirm_status(ReleaseStatus::Beta) == Response::No{
                             println!("Canceling upgrade");
-                            return Ok(());
+                            return Ok(UpgradeReport::new(&pack, requested_game_version, options.dry_run, false, reports));
                         }
                     },
                     _ => {}
                 }
 
-                println!("Enter new pack name (leave blank to keep old name):");
-                let new_name = readln!();
-                let new_name = new_name.trim();
+                let mut changes = vec![];
+                if options.interactive() {
+                    println!("Enter new pack name (leave blank to keep old name):");
+                    let new_name = readln!();
+                    let new_name = new_name.trim();
 
-                if !new_name.is_empty(){
-                    pack.name = new_name.to_owned();
+                    if !new_name.is_empty(){
+                        changes.extend(pack.set_field(PackField::Name(new_name.to_owned())));
+                    }
                 }
 
-                //TODO: smarter dedup, where we keep the higher version number of mods with colliding ids
-                //dedup via hashset
-                pack.mods = compatible.into_iter().collect::<std::collections::HashSet<_>>().into_iter().collect();
+                pack.mods = compatible;
+                for change in pack.normalize() {
+                    println!("{}", change);
+                }
+                changes.extend(pack.set_field(PackField::Version(requested_game_version.clone())));
+                //each mod moves to the file found for the new version, keeping its settings
+                for source in found_files {
+                    changes.extend(pack.replace_mod(source));
+                }
+                for change in &changes {
+                    println!("{}", change);
+                }
+                pack.bump_version_if_versioned(VersionBump::Major, changes.iter().map(ToString::to_string).collect());
 
-                let mut file = tokio::fs::File::create(pack_path.clone()).await.context(MissingPack{pack_file: pack_path}).erased()?;
-                crate::async_json::write_pretty(&mut file, &pack).await.context(error::AsyncJson)?;
-                return Ok(());
+                if !options.dry_run {
+                    pack.save(std::path::Path::new(&pack_path)).await?;
+                }
+                return Ok(UpgradeReport::new(&pack, requested_game_version, options.dry_run, !options.dry_run, reports));
             }
         }else{
             let percent_compatible = (compatible.len() as f64)/(total as f64) * 100.0;
@@ -398,9 +507,136 @@ fn new_version(
             });
             for modd in incompatible{
                 format_coloredln!((*COLOR_OUTPUT); (&WARN_COLOR){"\t {} ( {} )",modd.identifier_string(),modd.guess_project_url().unwrap_or_else(|| "COULD NOT GUESS PROJECT URL".to_owned()) });
+                let alternatives = reports.iter().filter(|report| report.key == modd.key()).flat_map(|report| report.alternatives.iter());
+                for alternative in alternatives {
+                    println!("\t\t alternative: {} ( https://www.curseforge.com/minecraft/mc-mods/{} )", alternative.name, alternative.id);
+                }
+            }
+        }
+        Ok(UpgradeReport::new(&pack, requested_game_version, options.dry_run, false, reports))
+    }
+}
+
+///How many mods are looked up at once
+const CONCURRENT_LOOKUPS: usize = 16;
+
+///What looking up one mod found, before deciding what to do about it
+struct Lookup{
+    entry: ModEntry,
+    ///The mod's key in the pack, which differs from the entry's if its project was renamed
+    previous_key: String,
+    ///Set when the project was deleted or archived
+    gone: Option<Outcome>,
+    ///The newest version the mod's settings allow
+    available: Option<String>,
+    ///The mod moved to `available`, when that differs from what the pack has
+    upgraded: Option<ModSource>,
+    ///Whether `upgraded` is a newer version, rather than a dynamic version recording what it resolves to
+    newer: bool,
+    ///Where the new version can be seen, if we know
+    link: Option<String>,
+}
+
+fn look_up(
+    entry: ModEntry,
+    target_game_version: semver::VersionReq,
+    release_status: ReleaseStatus,
+    http_client: HttpSimple,
+    repositories: maven::RepositoryConfig,
+    manager: download::Manager,
+) -> impl Future<Output=Result<Lookup, error::Error>> + Send + 'static {
+    async move{
+        let previous_key = entry.key();
+        let entry = match follow_project(entry, http_client.clone()).await? {
+            Followed::Found(entry) => entry,
+            Followed::Gone(entry, outcome) => return Ok(Lookup{
+                previous_key,
+                gone: Some(outcome),
+                available: None,
+                upgraded: None,
+                newer: false,
+                link: None,
+                entry,
+            }),
+        };
+        match entry.source.clone() {
+            ModSource::CurseforgeMod(curse_mod) => {
+                let found = find_most_recent(curse_mod.clone(),
+                                        target_game_version,
+                                        http_client,
+                                        release_status,
+                                        entry.constraint.clone()).await?;
+                Ok(match found {
+                    Some(found) => {
+                        let newer = found.modd.version > curse_mod.version;
+                        Lookup{
+                            previous_key,
+                            gone: None,
+                            available: Some(found.modd.version.to_string()),
+                            link: Some(found.download_url.to_string()),
+                            upgraded: if newer { Some(found.modd.into()) } else { None },
+                            newer,
+                            entry,
+                        }
+                    }
+                    None => Lookup{ previous_key, gone: None, available: None, upgraded: None, newer: false, link: None, entry },
+                })
+            }
+            ModSource::MavenMod { repo, artifact, resolved_version } => {
+                let current = resolved_version.clone().unwrap_or_else(|| artifact.version.clone());
+                let dynamic = VersionRequest::parse(&artifact.version).map(|request| request.is_dynamic()).unwrap_or(false);
+                let repo_uri = Uri::from_str(&repo).context(error::Uri)?;
+                let repos = repositories.repos_for(&artifact.group, Some(&repo_uri));
+                let newest = match entry.constraint.clone() {
+                    //the newest version within the constraint
+                    Some(constraint) => maven::Artifact {
+                        version: constraint.as_str().to_owned(),
+                        ..artifact.clone()
+                    }.resolve_version(repos, manager).await,
+                    None => artifact.clone().newest_version(repos, manager).await,
+                }.context(error::Maven)?;
+                let newer = ComparableVersion::new(&newest) > ComparableVersion::new(&current);
+                let upgraded = if dynamic {
+                    //keep the requested version, just record what it resolves to now
+                    ModSource::MavenMod {
+                        repo,
+                        artifact,
+                        resolved_version: Some(newest.clone()),
+                    }
+                } else {
+                    ModSource::MavenMod {
+                        repo,
+                        artifact: maven::Artifact {
+                            version: newest.clone(),
+                            ..artifact
+                        },
+                        resolved_version: None,
+                    }
+                };
+                let upgraded = if newer || (dynamic && resolved_version.is_none()) {
+                    Some(upgraded)
+                } else {
+                    None
+                };
+                Ok(Lookup{ previous_key, gone: None, available: Some(newest), upgraded, newer, link: None, entry })
             }
         }
-        Ok(())
+    }
+}
+
+fn print_updates(updates: &[Lookup]) {
+    let key_width = updates.iter().map(|update| update.entry.key().len()).chain(Some("MOD".len())).max().unwrap_or(0);
+    let current_width = updates.iter().map(|update| update.entry.version_string().len()).chain(Some("CURRENT".len())).max().unwrap_or(0);
+    println!("{:>3}  {:key_width$}  {:current_width$}  AVAILABLE", "#", "MOD", "CURRENT", key_width = key_width, current_width = current_width);
+    for (i, update) in updates.iter().enumerate() {
+        println!("{:>3}  {:key_width$}  {:current_width$}  {}{}",
+            i + 1,
+            update.entry.key(),
+            update.entry.version_string(),
+            update.available.as_ref().map(String::as_str).unwrap_or(""),
+            update.link.as_ref().map(|link| format!(" ({})", link)).unwrap_or_default(),
+            key_width = key_width,
+            current_width = current_width);
     }
 }
 
@@ -408,62 +644,119 @@ fn same_version(
     pack_path: String,
     mut pack: ModpackConfig,
     release_status: ReleaseStatus,
-) -> impl Future<Output=Result<(), error::Error>> + Send + 'static {
+    options: Options,
+) -> impl Future<Output=Result<UpgradeReport, error::Error>> + Send + 'static {
     let http_client = HttpSimple::new();
+    let repositories = pack.repository_config();
+    let manager = download::Manager::new().with_repositories(repositories.clone());
 
     let target_game_version = pack.version.clone();
+    let (skipped, checked): (ModList, ModList) = pack.mods.iter().cloned().partition(|entry| options.policy.skips(entry));
+
+    let mprog = Arc::new(MultiProgress::new());
+    let is_curse = |entry: &ModEntry| match entry.source {
+        ModSource::CurseforgeMod(_) => true,
+        ModSource::MavenMod{..} => false,
+    };
+    let curse_progress = mprog.add(ProgressBar::new(checked.iter().filter(|&entry| is_curse(entry)).count() as u64));
+    curse_progress.set_style(bar_style());
+    curse_progress.set_prefix("Checking curseforge mods");
+    let maven_progress = mprog.add(ProgressBar::new(checked.iter().filter(|&entry| !is_curse(entry)).count() as u64));
+    maven_progress.set_style(bar_style());
+    maven_progress.set_prefix("Checking maven mods");
+    let bars = vec![curse_progress.clone(), maven_progress.clone()];
+
+    let game_version = target_game_version.clone();
+    let policy = options.policy.clone();
+    let lookups = futures::stream::iter(checked)
+        .map(move |entry|{
+            let progress = if is_curse(&entry) { curse_progress.clone() } else { maven_progress.clone() };
+            let status = policy.limit(entry.upgrade_release_status(release_status));
+            look_up(entry, game_version.clone(), status, http_client.clone(), repositories.clone(), manager.clone())
+                .map_ok(move |lookup|{
+                    progress.inc(1);
+                    progress.set_message(&lookup.entry.key());
+                    lookup
+                })
+        })
+        .buffer_unordered(CONCURRENT_LOOKUPS);
 
     async move{
+        let t_handle = std::thread::spawn(move ||{
+            mprog.join().unwrap();
+        });
+        let mut lookups: Vec<Lookup> = lookups.try_collect().await?;
+        for bar in bars {
+            bar.finish_with_message("Done");
+        }
+        t_handle.join().unwrap();
+        lookups.sort_by_key(|lookup| lookup.entry.key());
+
         let mut new_mods = vec![];
-        //FIXME: ideally we would borrow pack.mods to iterate over it, but for now we can't due to
-        //       borrow tracing limitations in generators
-        let old_mods = futures::future::try_join_all(update_project_names(pack.mods.clone())).await?;
-        for modd in old_mods{
-            let updated = match modd {
-                ModSource::CurseforgeMod(curse_mod) => {
-                    let found = find_most_recent(curse_mod.clone(),
-                                            target_game_version.clone(),
-                                            http_client.clone(),
-                                            release_status).await?;
-                    if let Some(found) = found {
-                        assert_eq!(curse_mod.id, found.modd.id);
-                        if found.modd.version > curse_mod.version {
-                            let prompt = format!("Replace {} {} with {} ({})?",
-                                curse_mod.id,
-                                curse_mod.version,
-                                found.modd.version,
-                                found.download_url);
-                            if prompt_yes_no(&prompt,Response::Yes) == Response::Yes {
-                                Some(found.modd.into())
-                            } else {
-                                println!("\t skipping.");
-                                None
-                            }
-                        } else {
-                            println!("No update available for {}", curse_mod.id);
-                            None
-                        }
-                    } else {
-                        println!("Found no matching releases for {}", curse_mod.id);
-                        None
-                    }
-                }
-                mvn_mod @ ModSource::MavenMod { .. } => {
-                    println!("skipping maven mod: {:?}", mvn_mod);
-                    None
+        let mut changes = vec![];
+        let mut updates = vec![];
+        let mut reports: Vec<ModReport> = skipped.iter().map(|entry| ModReport::new(entry, None, Outcome::Skipped)).collect();
+        for lookup in lookups{
+            if let Some(outcome) = lookup.gone.clone() {
+                println!("{}: {}", lookup.entry.key(), outcome);
+                reports.push(ModReport::new(&lookup.entry, None, outcome));
+                continue;
+            }
+            if lookup.entry.key() != lookup.previous_key {
+                let change = pack.rename_mod(&lookup.previous_key, lookup.entry.source.clone()).erased()?;
+                println!("{}", change);
+                changes.push(format!("Renamed {} to {}", lookup.previous_key, lookup.entry.key()));
+            }
+            if lookup.available.is_none() {
+                println!("Found no matching releases for {}", lookup.entry.key());
+                reports.push(ModReport::new(&lookup.entry, None, Outcome::NotFound));
+            } else if !lookup.newer {
+                if let Some(upgraded) = lookup.upgraded.clone() {
+                    println!("Resolved {} to {}", lookup.entry.identifier_string(), upgraded.version_string());
+                    new_mods.push(upgraded);
                 }
-            };
-            if let Some(updated) = updated{
-                new_mods.push(updated);
+                reports.push(ModReport::new(&lookup.entry, lookup.available, Outcome::UpToDate));
+            } else if lookup.entry.pinned || options.policy.pins(&lookup.entry) {
+                println!("{} is pinned, not updating it to {}", lookup.entry.describe(), lookup.available.as_ref().map(String::as_str).unwrap_or(""));
+                reports.push(ModReport::new(&lookup.entry, lookup.available, Outcome::Pinned));
+            } else {
+                updates.push(lookup);
             }
         }
+
+        if updates.is_empty() {
+            println!("No updates available.");
+        } else {
+            print_updates(&updates);
+            let selection = options.choose_updates(updates.len());
+            for (i, update) in updates.into_iter().enumerate() {
+                let outcome = if selection.includes(i) {
+                    changes.push(format!("Updated {} from {} to {}",
+                        update.entry.key(),
+                        update.entry.version_string(),
+                        update.available.as_ref().map(String::as_str).unwrap_or("")));
+                    new_mods.push(update.upgraded.clone().expect("newer lookups always carry the upgraded mod"));
+                    Outcome::Updated
+                } else {
+                    Outcome::Declined
+                };
+                reports.push(ModReport::new(&update.entry, update.available, outcome));
+            }
+        }
+
         for modsource in new_mods {
-            pack.replace_mod(modsource);
+            for change in pack.replace_mod(modsource) {
+                println!("{}", change);
+            }
+        }
+        if !changes.is_empty() {
+            pack.bump_version_if_versioned(VersionBump::Patch, changes);
         }
 
-        let mut file = tokio::fs::File::create(pack_path.clone()).await.context(MissingPack{pack_file: pack_path}).erased()?;
-        crate::async_json::write_pretty(&mut file, &pack).await.context(error::AsyncJson)?;
-        Ok(())
+        if !options.dry_run {
+            pack.save(std::path::Path::new(&pack_path)).await?;
+        }
+        Ok(UpgradeReport::new(&pack, target_game_version, options.dry_run, !options.dry_run, reports))
     }
 }
 
@@ -474,16 +767,31 @@ pub enum Error{
         pack_file: String,
         source: std::io::Error,
     },
-    #[snafu(display("pack file {} is in bad format", pack_file))]
+    #[snafu(display("pack file {} is in bad format: {}", pack_file, source))]
     BadPackfile{
         pack_file: String,
-        source: serde_json::Error,
+        source: crate::pack_format::Error,
     },
     #[snafu(display("{} was not a semver version requirement: {}", arg, source))]
     BadSemverReq{
         source: semver::ReqParseError,
         arg: String,
-    }
+    },
+    #[snafu(display("policy {} does not exist", path))]
+    MissingPolicy{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("policy file {} is in bad format: {}", path, source))]
+    BadPolicy{
+        path: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("could not write report {}: {}", path, source))]
+    WritingReport{
+        path: String,
+        source: std::io::Error,
+    },
 }
 
 
@@ -494,33 +802,68 @@ pub struct Args{
     pack_file: String,
     /// The minecraft version to upgrade to
     mc_version: Option<String>,
+    /// Accept every upgrade without asking, but only move the pack to beta or alpha mods when the policy allows them
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+    /// Check for upgrades without changing the pack
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+    /// A json file with `max_release_status`, `skip` and `pin` settings for this upgrade
+    #[structopt(long = "policy")]
+    policy: Option<String>,
+    /// Write a report of what was found and done to this file, as markdown (.md), html (.html) or json
+    #[structopt(long = "report")]
+    report: Option<String>,
+}
+
+fn load_policy(path: String) -> Result<UpgradePolicy, error::Error> {
+    let file = std::fs::File::open(&path).with_context(|| MissingPolicy{path: path.clone()}).erased()?;
+    Ok(serde_json::from_reader(file).with_context(|| BadPolicy{path: path.clone()}).erased()?)
+}
+
+fn write_report(path: String, report: &UpgradeReport) -> Result<(), error::Error> {
+    let rendered = upgrade_report::render(report, ReportFormat::for_path(Path::new(&path)));
+    std::fs::write(&path, rendered).with_context(|| WritingReport{path: path.clone()}).erased()?;
+    Ok(())
+}
+
+///Parses a minecraft version requirement from the command line, reading a bare version `x` as `~x`
+pub(super) fn parse_mc_version(ver: String) -> Result<semver::VersionReq, error::Error> {
+    let ver = if ver.chars()
+    .next()
+    .expect("mc_version should not have length 0 due to arg parser")
+    .is_numeric()
+    {
+        //interpret a versionreq of x as ~x
+        println!("Interpreting version {} as ~{}", ver, ver);
+        format!("~{}", ver)
+    } else {
+        ver
+    };
+    Ok(semver::VersionReq::parse(ver.as_str()).context(BadSemverReq{arg: ver.clone()}).erased()?)
 }
 
 pub async fn upgrade(args: Args) -> Result<(), error::Error>{
 
-    let Args{pack_file, mc_version} = args;
+    let Args{pack_file, mc_version, yes, dry_run, policy, report} = args;
 
-    let mut file = std::fs::File::open(&pack_file).with_context(|| MissingPack{pack_file: pack_file.clone()}).erased()?;
-    let pack: ModpackConfig = serde_json::from_reader(&mut file).with_context(|| BadPackfile{pack_file: pack_file.clone()}).erased()?;
+    let contents = std::fs::read_to_string(&pack_file).with_context(|| MissingPack{pack_file: pack_file.clone()}).erased()?;
+    let pack: ModpackConfig = PackFormat::for_path(Path::new(&pack_file)).parse(&contents).with_context(|| BadPackfile{pack_file: pack_file.clone()}).erased()?;
 
-    if let Some(ver) = mc_version{
-        let ver = if ver.chars()
-        .next()
-        .expect("mc_version should not have length 0 due to arg parser")
-        .is_numeric()
-        {
-            //interpret a versionreq of x as ~x
-            println!("Interpreting version {} as ~{}", ver, ver);
-            format!("~{}", ver)
-        } else {
-            ver.to_owned()
-        };
-        let ver = semver::VersionReq::parse(ver.as_str()).context(BadSemverReq{arg: ver}).erased()?;
+    let options = Options{
+        yes,
+        dry_run,
+        policy: policy.map(load_policy).transpose()?.unwrap_or_default(),
+    };
+
+    let upgrade_report = if let Some(ver) = mc_version{
+        let ver = parse_mc_version(ver)?;
         new_version(
             ver,
             pack_file,
             pack,
-        ).await
+            options,
+        ).await?
     }else{
         let release_status = pack.auto_update_release_status
             .with_context(|| error::AutoUpdateDisabled{
@@ -530,6 +873,12 @@ pub async fn upgrade(args: Args) -> Result<(), error::Error>{
             pack_file,
             pack,
             release_status,
-        ).await
+            options,
+        ).await?
+    };
+
+    if let Some(report) = report {
+        write_report(report, &upgrade_report)?;
     }
-}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    download::{self, HttpSimple},
+    mod_source::{IndirectableModpack, ModpackConfig},
+    pack_format::PackFormat,
+    pack_validate,
+    error::prelude::*,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("pack {} could not be read: {}", path, source))]
+    ReadingPack{
+        path: String,
+        source: crate::async_json::Error,
+    },
+    #[snafu(display("pack {} is invalid: {}", path, source))]
+    InvalidPack{
+        path: String,
+        source: crate::pack_format::Error,
+    },
+    #[snafu(display("found {} problems in {}", count, path))]
+    Problems{
+        path: String,
+        count: usize,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "validate", about = "Checks the provided pack file for mistakes, reporting where they are.")]
+pub struct Args{
+    /// The metadata file for the pack you wish to check, in json, toml or yaml, which may extend another pack
+    pack_file: PathBuf,
+    /// Skip the checks which need the network: that forge exists, that the icon can be fetched, and following packs which aren't local files
+    #[structopt(long = "offline")]
+    offline: bool,
+}
+
+pub async fn validate(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, offline} = args;
+    let path = pack_file.display().to_string();
+
+    let mut file = tokio::fs::File::open(&pack_file).await.context(error::Io)?;
+    let contents = crate::async_json::read_string(&mut file).await.context(ReadingPack{path: path.clone()}).erased()?;
+    //not `ModpackConfig::load`, which would normalize away duplicates before they can be reported
+    let pack = match IndirectableModpack::parse(PackFormat::for_path(&pack_file), &contents).context(InvalidPack{path: path.clone()}).erased()? {
+        IndirectableModpack::Real(pack) => pack,
+        //overlays and channels are checked as the pack they make
+        _ => ModpackConfig::load_unnormalized(&pack_file, offline).await?,
+    };
+
+    let mut problems = pack_validate::check(&pack);
+    if !offline {
+        let manager = download::Manager::new().with_repositories(pack.repository_config());
+        problems.extend(pack_validate::check_online(pack, HttpSimple::new(), manager).await);
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("{} is valid", path);
+        Ok(())
+    } else {
+        Problems{path, count: problems.len()}.fail().erased()
+    }
+}
@@ -0,0 +1,237 @@
+use futures::prelude::*;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    cmds::Outcome,
+    curseforge::{self, ReleaseStatus},
+    download::HttpSimple,
+    mod_source::{ModpackConfig, ModSource},
+    error::prelude::*,
+};
+
+///How many mods are looked up at once
+const CONCURRENT_LOOKUPS: usize = 16;
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("Io error {} while reading previous results {}", source, path))]
+    ReadingResults{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Io error {} while writing results {}", source, path))]
+    WritingResults{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Previous results {} are in bad format: {}", path, source))]
+    BadResults{
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+///The most stable release of each curseforge mod for the new version, or None if it has no files for it yet
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct WatchResults{
+    checked_at: Option<String>,
+    mods: BTreeMap<String, Option<ReleaseStatus>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReadinessChange{
+    NewlyCompatible{
+        key: String,
+        status: ReleaseStatus,
+    },
+    Promoted{
+        key: String,
+        from: ReleaseStatus,
+        to: ReleaseStatus,
+    },
+    ///Files for the version were removed, or demoted
+    Regressed{
+        key: String,
+        from: ReleaseStatus,
+        to: Option<ReleaseStatus>,
+    },
+}
+
+impl std::fmt::Display for ReadinessChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ReadinessChange::NewlyCompatible{ref key, status} => write!(f, "NEWLY COMPATIBLE: {} ({})", key, status.value()),
+            ReadinessChange::Promoted{ref key, from, to} => write!(f, "PROMOTED: {} {} -> {}", key, from.value(), to.value()),
+            ReadinessChange::Regressed{ref key, from, to} => write!(f, "REGRESSED: {} {} -> {}", key, from.value(), to.map(ReleaseStatus::value).unwrap_or("incompatible")),
+        }
+    }
+}
+
+///What changed for mods that were checked both times
+fn changes_since(previous: &WatchResults, current: &WatchResults) -> Vec<ReadinessChange> {
+    current.mods.iter()
+        .filter_map(|(key, &now)| {
+            let before = *previous.mods.get(key)?;
+            let key = key.clone();
+            match (before, now) {
+                (None, Some(status)) => Some(ReadinessChange::NewlyCompatible{ key, status }),
+                (Some(from), Some(to)) if from != to && from.accepts(to) => Some(ReadinessChange::Promoted{ key, from, to }),
+                (Some(from), to) if Some(from) != to => Some(ReadinessChange::Regressed{ key, from, to }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+///The most stable of `statuses`
+fn most_stable(statuses: &[ReleaseStatus]) -> Option<ReleaseStatus> {
+    [ReleaseStatus::Release, ReleaseStatus::Beta, ReleaseStatus::Alpha].iter()
+        .cloned()
+        .find(|status| statuses.contains(status))
+}
+
+fn results_path(pack_name: &str, version: &semver::VersionReq) -> PathBuf {
+    let mut path = app_dirs::app_dir(app_dirs::AppDataType::UserData, crate::APP_INFO, "upgrade_watch").expect("Data directory must be accesible");
+    let name: String = format!("{}_{}", pack_name, version)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    path.push(format!("{}.json", name));
+    path
+}
+
+fn load_results(path: &Path) -> Result<Option<WatchResults>, Error> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(ReadingResults{path: path.display().to_string()}.into_error(e)),
+    };
+    serde_json::from_slice(&contents).map(Some).context(BadResults{path: path.display().to_string()})
+}
+
+fn save_results(path: &Path, results: &WatchResults) -> Result<(), Error> {
+    let contents = serde_json::to_vec_pretty(results).context(BadResults{path: path.display().to_string()})?;
+    std::fs::write(path, contents).context(WritingResults{path: path.display().to_string()})
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "watch-upgrade", about = "Reports which mods have become ready for a new minecraft version since the last run. Exits with code 2 until every mod is ready.")]
+pub struct Args{
+    /// The metadata json file for the pack you wish to check
+    pack_file: PathBuf,
+    /// The minecraft version to check readiness for
+    mc_version: String,
+}
+
+pub async fn watch_upgrade(args: Args) -> Result<Outcome,crate::Error>
+{
+    let Args{pack_file, mc_version} = args;
+
+    let pack = ModpackConfig::load(&pack_file).await?;
+    let version = super::upgrade::parse_mc_version(mc_version)?;
+    let required_status = pack.auto_update_release_status.unwrap_or(ReleaseStatus::Release);
+
+    let mut curse_mods = vec![];
+    let mut unchecked = vec![];
+    for entry in &pack.mods {
+        match entry.source {
+            ModSource::CurseforgeMod(ref curse_mod) => curse_mods.push(curse_mod.clone()),
+            ModSource::MavenMod{..} => unchecked.push(entry.identifier_string()),
+        }
+    }
+
+    let http_client = HttpSimple::new();
+    let lookup_version = version.clone();
+    let mods: BTreeMap<String, Option<ReleaseStatus>> = futures::stream::iter(curse_mods)
+        .map(move |curse_mod| {
+            let key = curse_mod.id.clone();
+            curseforge::api::all_for_version(curse_mod, http_client.clone(), lookup_version.clone())
+                .map_ok(|release| release.release_status)
+                .try_collect::<Vec<_>>()
+                .map_ok(move |statuses| (key, most_stable(&statuses)))
+        })
+        .buffer_unordered(CONCURRENT_LOOKUPS)
+        .try_collect()
+        .await?;
+    let current = WatchResults{
+        checked_at: Some(chrono::Local::now().to_rfc3339()),
+        mods,
+    };
+
+    let path = results_path(&pack.name, &version);
+    match load_results(&path).erased()? {
+        Some(previous) => {
+            let changes = changes_since(&previous, &current);
+            println!("Since the last check ({}):", previous.checked_at.as_ref().map(String::as_str).unwrap_or("unknown"));
+            if changes.is_empty() {
+                println!("\tnothing changed");
+            }
+            for change in changes {
+                println!("\t{}", change);
+            }
+        }
+        None => println!("No previous results for minecraft {}, recording these for next time", version),
+    }
+    save_results(&path, &current).erased()?;
+
+    for identifier in &unchecked {
+        println!("you must check maven mod: {}", identifier);
+    }
+
+    let total = current.mods.len();
+    let ready = current.mods.values()
+        .filter(|status| status.map_or(false, |status| required_status.accepts(status)))
+        .count();
+    if ready == total {
+        println!("All {} curseforge mods are ready for minecraft {}", total, version);
+        Ok(Outcome::Done)
+    } else {
+        println!("{} of {} curseforge mods are ready for minecraft {}", ready, total, version);
+        Ok(Outcome::NotReady)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn results(mods: &[(&str, Option<ReleaseStatus>)]) -> WatchResults {
+        WatchResults{
+            checked_at: None,
+            mods: mods.iter().map(|&(key, status)| (key.to_owned(), status)).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_new_compatibility_and_promotions() {
+        let previous = results(&[
+            ("jei", None),
+            ("baubles", Some(ReleaseStatus::Beta)),
+            ("mekanism", Some(ReleaseStatus::Release)),
+            ("botania", Some(ReleaseStatus::Alpha)),
+        ]);
+        let current = results(&[
+            ("jei", Some(ReleaseStatus::Alpha)),
+            ("baubles", Some(ReleaseStatus::Release)),
+            ("mekanism", None),
+            ("botania", Some(ReleaseStatus::Alpha)),
+            ("tinkers-construct", Some(ReleaseStatus::Release)),
+        ]);
+        assert_eq!(changes_since(&previous, &current), vec![
+            ReadinessChange::Promoted{ key: "baubles".into(), from: ReleaseStatus::Beta, to: ReleaseStatus::Release },
+            ReadinessChange::NewlyCompatible{ key: "jei".into(), status: ReleaseStatus::Alpha },
+            ReadinessChange::Regressed{ key: "mekanism".into(), from: ReleaseStatus::Release, to: None },
+        ]);
+    }
+
+    #[test]
+    fn finds_the_most_stable_release() {
+        assert_eq!(most_stable(&[ReleaseStatus::Alpha, ReleaseStatus::Beta]), Some(ReleaseStatus::Beta));
+        assert_eq!(most_stable(&[ReleaseStatus::Alpha, ReleaseStatus::Release]), Some(ReleaseStatus::Release));
+        assert_eq!(most_stable(&[]), None);
+    }
+}
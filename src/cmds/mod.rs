@@ -1,9 +1,32 @@
 mod update;
+mod status;
+mod uninstall;
 pub mod dev;
+pub mod cache;
 pub use self::update::*;
+pub use self::status::status;
+pub use self::uninstall::uninstall;
 
 use structopt::StructOpt;
 
+///How a command finished, when it didn't fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome{
+    Done,
+    ///The command worked, but what it checks for isn't the case yet, such as mods which aren't ready for a new minecraft version
+    NotReady,
+}
+
+impl Outcome{
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Done => 0,
+            //1 is left for failures
+            Outcome::NotReady => 2,
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "modpacktool-update", version = "0.1", author = "E. Rochester <euan@rochester.me.uk>")]
 pub enum Args{
@@ -11,17 +34,33 @@ pub enum Args{
     Dev(dev::Args),
     #[structopt(name="update", visible_alias = "install")]
     Update(update::Args),
+    #[structopt(name="status")]
+    Status(status::Args),
+    #[structopt(name="uninstall")]
+    Uninstall(uninstall::Args),
+    #[structopt(name="cache")]
+    Cache(cache::Args),
 }
 impl Args{
-    pub async fn dispatch(self, log: slog::Logger) -> crate::Result<()>
+    pub async fn dispatch(self, log: slog::Logger) -> crate::Result<Outcome>
     {
         match self{
             Args::Update(update_args) => {
                 update_args.dispatch(log).await
             }
+            Args::Status(status_args) => {
+                status(status_args).await
+            }
+            Args::Uninstall(uninstall_args) => {
+                uninstall(uninstall_args).await
+            }
             Args::Dev(dev_args) => {
-                dev_args.dispatch(log).await
+                return dev_args.dispatch(log).await;
             }
-        }
+            Args::Cache(cache_args) => {
+                cache_args.dispatch(log).await
+            }
+        }?;
+        Ok(Outcome::Done)
     }
 }
\ No newline at end of file
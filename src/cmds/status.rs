@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    install_state::{self, InstallState, MODS_DIR},
+    mod_source::ModpackConfig,
+    error::prelude::*,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("Error while reading install state: {}", source))]
+    ReadingInstallState{
+        source: install_state::Error,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "status", about = "Shows what has changed since the pack was last installed or updated.")]
+pub struct Args{
+    /// The metadata json file for the installed pack
+    pack_file: PathBuf,
+    /// Read the pack without touching the network
+    #[structopt(long = "offline")]
+    offline: bool,
+}
+
+pub async fn status(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, offline} = args;
+
+    let pack = if offline {
+        ModpackConfig::load_offline(&pack_file).await?
+    } else {
+        ModpackConfig::load_maybe_indirected(&pack_file).await?
+    };
+    let pack_path = PathBuf::from(".").join(pack.folder());
+
+    let state = match InstallState::load(&pack_path).context(ReadingInstallState).erased()? {
+        Some(state) => state,
+        None => {
+            println!("{} isn't installed in {}", pack.name, pack_path.display());
+            return Ok(());
+        }
+    };
+
+    match (&state.pack_version, &pack.pack_version) {
+        (Some(installed), Some(current)) if installed != current => println!("{} {} is installed, the pack is now at {}", pack.name, installed, current),
+        (Some(installed), _) => println!("{} {} is installed", pack.name, installed),
+        (None, _) => println!("{} is installed", pack.name),
+    }
+    match state.pack_hash {
+        Some(ref hash) if *hash == install_state::hash_pack(&pack) => println!("The pack hasn't changed since it was installed"),
+        Some(_) => println!("The pack has changed since it was installed, run update to catch up"),
+        None => println!("The pack was installed before changes to it were tracked, run update to start tracking them"),
+    }
+    if let Some(ref forge) = state.forge_version_id {
+        println!("Forge: {}", forge);
+    }
+    if let Some(ref profile) = state.profile_key {
+        println!("Launcher profile: {}", profile);
+    }
+    for (key, version) in &state.maven_versions {
+        println!("Maven mod {}: {}", key, version);
+    }
+    for (feature, chosen) in &state.choices {
+        println!("Optional {}: {}", feature, if *chosen { "installed" } else { "left out" });
+    }
+
+    if state.files.is_empty() {
+        println!("No installed files were recorded, run update to start tracking them");
+        return Ok(());
+    }
+    let current = install_state::scan(&pack_path, MODS_DIR).context(ReadingInstallState).erased()?;
+    let changes = state.changes(&current);
+    if changes.is_empty() {
+        println!("No installed files have changed");
+    } else {
+        println!("Changed files:");
+        for change in changes {
+            println!("  {}", change);
+        }
+    }
+    Ok(())
+}
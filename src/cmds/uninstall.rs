@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use snafu::Snafu;
+use crate::{
+    install_state::{self, InstallState, MODS_DIR},
+    mod_source::ModpackConfig,
+    error::prelude::*,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("{} isn't installed in {}", name, path))]
+    NotInstalled{
+        name: String,
+        path: String,
+    },
+    #[snafu(display("Error while reading install state: {}", source))]
+    ReadingInstallState{
+        source: install_state::Error,
+    },
+    #[snafu(display("The install state lists {}, which isn't a file in the mods folder", file))]
+    UnexpectedFile{
+        file: String,
+    },
+    #[snafu(display("Error {} while removing file: {}", source, file))]
+    RemovingInstalledFile{
+        file: String,
+        source: std::io::Error,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "uninstall", about = "Removes the files and launcher profile the pack was installed with, keeping saves and settings.")]
+pub struct Args{
+    /// The metadata json file for the installed pack
+    pack_file: PathBuf,
+    /// Also remove installed files which have been changed since they were installed
+    #[structopt(long = "force")]
+    force: bool,
+    /// Read the pack without touching the network
+    #[structopt(long = "offline")]
+    offline: bool,
+}
+
+pub async fn uninstall(args: Args) -> Result<(),crate::Error>
+{
+    let Args{pack_file, force, offline} = args;
+
+    let pack = if offline {
+        ModpackConfig::load_offline(&pack_file).await?
+    } else {
+        ModpackConfig::load_maybe_indirected(&pack_file).await?
+    };
+    let pack_path = PathBuf::from(".").join(pack.folder());
+    let state = InstallState::load(&pack_path).context(ReadingInstallState).erased()?
+        .context(NotInstalled{name: pack.name.clone(), path: pack_path.display().to_string()}).erased()?;
+
+    if let Some(name) = state.files.keys().find(|name| !install_state::is_file_in(name, MODS_DIR)) {
+        return UnexpectedFile{file: name.clone()}.fail().erased();
+    }
+
+    let current = install_state::scan(&pack_path, MODS_DIR).context(ReadingInstallState).erased()?;
+    let mut kept = 0;
+    for (name, hash) in &state.files {
+        match current.get(name) {
+            None => continue,
+            Some(current_hash) if current_hash != hash && !force => {
+                println!("Keeping {}, which was changed since it was installed", name);
+                kept += 1;
+                continue;
+            }
+            Some(_) => {}
+        }
+        let path = pack_path.join(name);
+        std::fs::remove_file(&path).context(RemovingInstalledFile{file: path.display().to_string()}).erased()?;
+    }
+
+    if let Some(ref key) = state.profile_key {
+        if !super::update::remove_launcher_profile(key).await? {
+            println!("The launcher profile {} was already gone", key);
+        }
+    }
+    InstallState::remove(&pack_path).context(ReadingInstallState).erased()?;
+    //these only go if they're empty, so saves, settings and the player's own mods stay
+    let _ = std::fs::remove_dir(pack_path.join(MODS_DIR));
+    let _ = std::fs::remove_dir(&pack_path);
+
+    println!("Uninstalled {}", pack.name);
+    if kept > 0 {
+        println!("Kept {} changed files, pass --force to remove them too", kept);
+    }
+    if let Some(ref forge) = state.forge_version_id {
+        println!("Forge {} was left installed, as other packs may use it", forge);
+    }
+    Ok(())
+}
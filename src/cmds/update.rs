@@ -3,7 +3,10 @@ use futures::{
     prelude::*,
 };
 use slog::Logger;
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+};
 use serde_json::{self, Value};
 use tokio;
 use std;
@@ -15,15 +18,18 @@ use crate::{
     download::{self, DownloadMulti},
     hacks,
     maven,
+    mc_libs,
     cache::Cacheable,
     util,
-    mod_source::{ModpackConfig,ModList},
+    install_state::{self, Files, InstallState, MODS_DIR},
+    mod_source::{ModpackConfig,ModSource,PackSelector},
+    optional_mods::{self, Choices, Selection},
     error::prelude::*,
 };
 use indicatif::{MultiProgress,ProgressBar,ProgressStyle};
 use std::sync::Arc;
 
-fn bar_style() -> ProgressStyle{
+pub(crate) fn bar_style() -> ProgressStyle{
     ProgressStyle::default_bar()
         .template("{prefix:23.bold.dim} {spinner:.green} [{elapsed_precise}] {wide_bar} {pos:>3}/{len:3} {msg:!}")
 }
@@ -68,11 +74,6 @@ pub enum Error{
     CopyingVersionJson{
         source: std::io::Error,
     },
-    #[snafu(display("pack {} does not exist", pack_file))]
-    MissingPack{
-        pack_file: String,
-        source: std::io::Error,
-    },
     #[snafu(display("Error while installing forge: {}", source))]
     InstallingForge{
         source: crate::cache::Error,
@@ -102,38 +103,114 @@ pub enum Error{
     #[snafu(display("Error while hacking forge veresion json: {}", source))]
     HackForgeVersionJson{
         source: crate::hacks::Error,
-    }
+    },
+    #[snafu(display("Error while fetching forge's libraries: {}", source))]
+    CachingLibraries{
+        source: crate::mc_libs::Error,
+    },
+    #[snafu(display("Error while recording install state: {}", source))]
+    RecordingInstallState{
+        source: crate::install_state::Error,
+    },
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "update", about = "Updates the on-disk mods from the provided pack file.")]
 pub struct Args{
     /// The metadata json file for the pack you wish to update
-    pub pack_file: PathBuf,
+    #[structopt(required_unless = "from_bundle")]
+    pub pack_file: Option<PathBuf>,
+    /// Install purely from the local cache, without touching the network
+    #[structopt(long = "offline")]
+    pub offline: bool,
+    /// Install from a bundle created by `dev bundle`, without touching the network
+    #[structopt(long = "from-bundle", conflicts_with = "pack_file")]
+    pub from_bundle: Option<PathBuf>,
+    /// The release channel to install from, for packs that have them (e.g. stable or beta)
+    #[structopt(long = "channel")]
+    pub channel: Option<String>,
+    /// Pin the install to a specific pack version
+    #[structopt(long = "pack-version", conflicts_with = "channel")]
+    pub pack_version: Option<semver::Version>,
+    /// Install an optional mod, or group of them. Remembered for later updates, and may be given more than once
+    #[structopt(long = "with")]
+    pub with: Vec<String>,
+    /// Leave out an optional mod, or group of them. Remembered for later updates, and may be given more than once
+    #[structopt(long = "without")]
+    pub without: Vec<String>,
+    /// Don't ask about optional mods which haven't been chosen yet, leaving them out. Nothing is asked when stdin isn't a terminal
+    #[structopt(long = "no-prompt")]
+    pub no_prompt: bool,
 }
 
 impl Args{
     pub async fn dispatch(self, log: slog::Logger) -> crate::Result<()>
     {
-        if !self.pack_file.exists(){
-            eprintln!("{:?} is not an accessible path",self.pack_file);
+        let selection = Selection{
+            with: self.with,
+            without: self.without,
+            ask: !self.no_prompt,
+        };
+        if let Some(bundle_path) = self.from_bundle {
+            let pack = crate::bundle::import_bundle(&bundle_path, &log).erased()?;
+            return update_offline(pack, selection, log).await;
+        }
+        let pack_file = self.pack_file.expect("pack_file is required without a bundle");
+        if !pack_file.exists(){
+            eprintln!("{:?} is not an accessible path",pack_file);
             Ok(())
-        } else if !self.pack_file.is_file(){
-            eprintln!("No file exists at the path {:?}",self.pack_file);
+        } else if !pack_file.is_file(){
+            eprintln!("No file exists at the path {:?}",pack_file);
             Ok(())
         }else{
-            let mut file = tokio::fs::File::open(self.pack_file.clone()).await.context(MissingPack{pack_file: self.pack_file.display().to_string()}).erased()?;
-            let pack = ModpackConfig::load_maybe_indirected(&mut file).await?;
-            update(pack,log).await
+            let selector = PackSelector{
+                channel: self.channel,
+                version: self.pack_version,
+            };
+            if self.offline {
+                let pack = ModpackConfig::load_offline_with(&pack_file, selector).await?;
+                update_offline(pack, selection, log).await
+            } else {
+                let pack = ModpackConfig::load_maybe_indirected_with(&pack_file, selector).await?;
+                update(pack, selection, log).await
+            }
         }
     }
 }
 
-pub fn update(pack: ModpackConfig, log: Logger) -> impl Future<Output=crate::Result<()>> {
+///Works out which optional mods to install: the choices remembered from the last install, changed by `selection`
+fn choose_optional_mods(pack: &ModpackConfig, selection: &Selection) -> crate::Result<Choices> {
+    let pack_path = PathBuf::from(".").join(pack.folder());
+    let previous = InstallState::load(&pack_path).context(RecordingInstallState).erased()?;
+    let mut choices = previous.map(|state| state.choices).unwrap_or_default();
+    let features = optional_mods::features(&pack.mods);
+    optional_mods::choose(&mut choices, &features, &selection.with, &selection.without).erased()?;
+    if selection.ask {
+        optional_mods::ask_undecided(&mut choices, &features);
+    }
+    Ok(choices)
+}
+
+///Installs `pack` using only the local cache, failing early with a list of anything that isn't cached
+pub async fn update_offline(pack: ModpackConfig, selection: Selection, log: Logger) -> crate::Result<()> {
+    let choices = choose_optional_mods(&pack, &selection)?;
+    let missing = pack.uncached_items(&choices);
+    if !missing.is_empty() {
+        return error::MissingFromCache{missing}.fail();
+    }
+    update_with(pack, download::Manager::new().with_offline(true), choices, log).await
+}
+
+pub async fn update(pack: ModpackConfig, selection: Selection, log: Logger) -> crate::Result<()> {
+    let choices = choose_optional_mods(&pack, &selection)?;
+    update_with(pack, download::Manager::new(), choices, log).await
+}
+
+pub fn update_with(pack: ModpackConfig, download_manager: download::Manager, choices: Choices, log: Logger) -> impl Future<Output=crate::Result<()>> {
+    let download_manager = download_manager.with_repositories(pack.repository_config());
 
     let mprog = Arc::new(MultiProgress::new());
     mprog.set_draw_target(indicatif::ProgressDrawTarget::hidden());
-    let download_manager = download::Manager::new();
 
     let mprog_runner = mprog.clone();
 
@@ -147,26 +224,62 @@ pub fn update(pack: ModpackConfig, log: Logger) -> impl Future<Output=crate::Res
         let mut pack_path = PathBuf::from(".");
         let forge_maven_artifact = pack.forge_maven_artifact();
         pack_path.push(pack.folder());
-        let ModpackConfig { name: pack_name, mods, icon, .. } = pack;
+        let pack_hash = install_state::hash_pack(&pack);
+        let ModpackConfig { name: pack_name, mods, icon, pack_version, .. } = pack;
+
+        let previous = InstallState::load(&pack_path).context(RecordingInstallState).erased()?;
+        let (previous_version, previous_files) = match previous {
+            Some(state) => (state.pack_version, Some(state.files)),
+            None => (None, None),
+        };
+        report_versions(&pack_name, previous_version, pack_version.as_ref());
 
         let install_fut = install_forge(
                             forge_maven_artifact,
                             download_manager.clone(),
                             &log).erased();
 
-        let download_mods_fut = download_modlist(pack_path.clone(), mods, download_manager.clone(), &log, mprog.clone());
+        let mut client_mods = vec![];
+        let mut maven_versions = BTreeMap::new();
+        for entry in mods.into_iter().filter(|entry| entry.side.on_client() && optional_mods::is_chosen(entry, &choices)) {
+            let source = entry.source.with_resolved_version(download_manager.clone()).await.context(error::Download)?;
+            if let ModSource::MavenMod{..} = source {
+                maven_versions.insert(source.key(), source.version_string());
+            }
+            client_mods.push(source);
+        }
+        let download_mods_fut = download_modlist(pack_path.clone(), client_mods, previous_files, download_manager.clone(), &log, mprog.clone());
 
-        let (id, _) = futures::try_join!(
+        let (id, files) = futures::try_join!(
             install_fut,
             download_mods_fut
         )?;
-        add_launcher_profile(&pack_path, pack_name, id, icon, &log, progress).erased()?.await.erased()?;
+        let forge_version_id = id.0.clone();
+        add_launcher_profile(&pack_path, pack_name.clone(), id, icon, &log, progress).erased()?.await.erased()?;
+        InstallState{
+            pack_version,
+            choices,
+            pack_hash: Some(pack_hash),
+            files,
+            profile_key: Some(pack_name),
+            forge_version_id: Some(forge_version_id),
+            maven_versions,
+        }.save(&pack_path).context(RecordingInstallState).erased()?;
         info!(log,"Done");
         t_handle.join().unwrap();
         Ok(())
     }
 }
 
+fn report_versions(pack_name: &str, previous: Option<semver::Version>, new: Option<&semver::Version>) {
+    match (previous, new) {
+        (Some(ref previous), Some(new)) if previous != new => println!("Updating {} from {} to {}", pack_name, previous, new),
+        (Some(_), Some(new)) => println!("Reinstalling {} {}", pack_name, new),
+        (None, Some(new)) => println!("Installing {} {}", pack_name, new),
+        (_, None) => println!("Installing {}", pack_name),
+    }
+}
+
 fn merge(a: &mut Value, b: &Value) {
     match (a, b) {
         (&mut Value::Object(ref mut a), &Value::Object(ref b)) => for (k, v) in b {
@@ -254,41 +367,78 @@ fn add_launcher_profile(
     )
 }
 
+///Takes the profile `key` out of the launcher, returning whether it was there
+pub(crate) async fn remove_launcher_profile(key: &str) -> crate::Result<bool> {
+    let mut mc_path = mc_install_loc();
+    mc_path.push("launcher_profiles.json");
+
+    let mut profiles_file = tokio::fs::File::open(mc_path.clone()).await.context(MissingProfilesJson).erased()?;
+    let mut launcher_profiles: Value = crate::async_json::read(&mut profiles_file).await.context(InvalidProfilesJson).erased()?;
+    let removed = launcher_profiles
+        .pointer_mut("/profiles")
+        .and_then(Value::as_object_mut)
+        .and_then(|profiles| profiles.remove(key))
+        .is_some();
+    if removed {
+        let mut out_file = tokio::fs::File::create(mc_path).await.context(MissingProfilesJson).erased()?;
+        crate::async_json::write_pretty(&mut out_file, &launcher_profiles).await.context(InvalidProfilesJson).erased()?;
+    }
+    Ok(removed)
+}
+
 fn download_modlist(
-    mut pack_path: PathBuf,
-    mod_list: ModList,
+    pack_path: PathBuf,
+    mod_list: Vec<ModSource>,
+    previous_files: Option<Files>,
     manager: download::Manager,
     log: &Logger,
     mprog: Arc<MultiProgress>,
-) -> impl Future<Output=crate::Result<()>> {
+) -> impl Future<Output=crate::Result<Files>> {
     let log = log.new(o!("stage"=>"download_modlist"));
 
     let progress = mprog.add(ProgressBar::new_spinner());
     progress.set_style(spinner_style());
 
     async move{
-        pack_path.push("mods");
+        let mods_path = pack_path.join(MODS_DIR);
         progress.set_prefix("creating mod directory");
-        tokio::fs::create_dir_all(pack_path.clone()).await.context(CreatingDirectory{directory: pack_path.display().to_string()}).erased()?;
+        tokio::fs::create_dir_all(mods_path.clone()).await.context(CreatingDirectory{directory: mods_path.display().to_string()}).erased()?;
 
         progress.set_prefix("enumerating mod directory");
-        let mut entry_stream = tokio::fs::read_dir(pack_path.clone()).await.context(EnumeratingDirectory{directory: pack_path.display().to_string()}).erased()?;
+        let existing = install_state::scan(&pack_path, MODS_DIR).context(RecordingInstallState).erased()?;
 
         progress.set_prefix("Removing old mod files");
-
-        while let Some(entry) = entry_stream.try_next().await.context(EnumeratingDirectory{directory: pack_path.display().to_string()}).erased()? {
+        //installs from before files were tracked can't tell ours from the player's, so clear them all as we used to
+        let previous_files = previous_files.filter(|files| !files.is_empty());
+        let mut kept = Files::new();
+        for (name, hash) in existing {
+            match previous_files.as_ref().map(|files| files.get(&name)) {
+                Some(None) => {
+                    println!("Keeping {}, which wasn't installed by the pack", name);
+                    kept.insert(name, hash);
+                    continue;
+                }
+                Some(Some(installed_hash)) if *installed_hash != hash => println!("Replacing {}, which was changed since it was installed", name),
+                _ => {}
+            }
+            let path = pack_path.join(&name);
             progress.inc(1);
-            progress.set_message(format!("Removing: {}",entry.path().to_str().unwrap()).as_str());
-            tokio::fs::remove_file(entry.path().clone()).await.context(RemovingFile{file: entry.path().display().to_string()}).erased()?;
+            progress.set_message(format!("Removing: {}", name).as_str());
+            tokio::fs::remove_file(path.clone()).await.context(RemovingFile{file: path.display().to_string()}).erased()?;
         }
         progress.finish_with_message("Done");
         let progress = mprog.add(ProgressBar::new(mod_list.len() as u64));
         progress.set_style(bar_style());
 
         progress.set_prefix("Downloading new mod files");
-        mod_list.download_all(pack_path, manager, log, progress.clone()).await.context(error::Download)?;
+        mod_list.download_all(mods_path, manager, log, progress.clone()).await.context(error::Download)?;
         progress.finish_with_message("Done");
-        Ok(())
+
+        let installed = install_state::scan(&pack_path, MODS_DIR).context(RecordingInstallState).erased()?
+            .into_iter()
+            .filter(|(name, hash)| kept.get(name) != Some(hash))
+            .collect();
+        Ok(installed)
     }
 }
 
@@ -343,14 +493,24 @@ fn install_forge(
                         &mut version_file.into_std()).context(CopyingVersionJson)?;
 
         debug!(log, "Applying version json hacks");
-        hacks::hack_forge_version_json(mc_path).context(HackForgeVersionJson)?;
+        hacks::hack_forge_version_json(mc_path, manager.repositories()).context(HackForgeVersionJson)?;
 
         let mut mc_path = mc_install_loc();
         mc_path.push("libraries");
         mc_path.push(forge_maven_artifact_path);
         mc_path.pop(); //pop the filename
 
-        forge_artifact.install_at_no_classifier(mc_path, manager, log).await.context(InstallingForge)?;
+        forge_artifact.clone().install_at_no_classifier(mc_path, manager.clone(), log.clone()).await.context(InstallingForge)?;
+
+        debug!(log, "Installing libraries");
+        let libraries = mc_libs::cache_libraries(forge_artifact, manager.clone(), log.clone()).await.context(CachingLibraries)?;
+        for library in libraries {
+            let mut mc_path = mc_install_loc();
+            mc_path.push("libraries");
+            mc_path.push(library.to_path());
+            mc_path.pop(); //pop the filename
+            library.install_at(&mc_path, manager.clone(), log.clone()).await.context(InstallingForge)?;
+        }
         Ok(VersionId(version_id))
     }
 }
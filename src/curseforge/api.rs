@@ -7,10 +7,12 @@ use std::{
     pin::Pin,
 };
 use http::Uri;
+use url::Url;
 use crate::{
     download::HttpSimple,
     curseforge::{self,ReleaseStatus},
     error::prelude::*,
+    mod_source::VersionConstraint,
 };
 
 trait SelectExt{
@@ -226,6 +228,44 @@ pub struct ReleaseInfo{
     pub modd: curseforge::Mod,
 }
 
+#[derive(Debug)]
+pub struct ModVersionInfo {
+    pub modd: curseforge::Mod,
+    pub download_url: Url,
+    pub release_status: ReleaseStatus,
+}
+
+///Finds the newest file of `curse_mod` for the game version that is at least as stable as `target_release_status`,
+///and whose file id is within `constraint` if there is one
+pub fn find_most_recent(
+    curse_mod: curseforge::Mod,
+    target_game_version: semver::VersionReq,
+    http_client: HttpSimple,
+    target_release_status: ReleaseStatus,
+    constraint: Option<VersionConstraint>,
+) -> impl Future<Output=Result<Option<ModVersionInfo>,crate::Error>> + Send {
+    let mut stream = Box::pin(all_for_version(curse_mod, http_client, target_game_version).try_filter(move |release_info| {
+        futures::future::ready(
+            target_release_status.accepts(release_info.release_status)
+                && constraint.as_ref().map_or(true, |constraint| constraint.allows(&release_info.modd.version.to_string()))
+                //already filtering by this on get
+                //&& game_versions.iter().any(|ver| target_game_version.matches(ver))
+        )
+    }));
+    async move{
+        Ok(if let Some(release_info) = stream.try_next().await?{
+            let url = Url::parse(&format!("https://www.curseforge.com/minecraft/mc-mods/{}/download/{}/file",release_info.modd.id,release_info.modd.version)).expect("bad prechecked url");
+            Some(ModVersionInfo {
+                modd: release_info.modd,
+                download_url: url,
+                release_status: release_info.release_status,
+            })
+        }else{
+            None
+        })
+    }
+}
+
 pub fn all_for_version(
     curse_mod: curseforge::Mod,
     http_client: HttpSimple,
@@ -249,7 +289,7 @@ pub fn all_for_version(
             };
             if page.len() >= 25{
                 //more pages
-                Some((Ok(page), Some(page_num)))
+                Some((Ok(page), Some(page_num + 1)))
             }else{
                 //done
                 Some((Ok(page),None))
@@ -270,7 +310,8 @@ async fn page_for_version(
     use std::io::Cursor;
     use kuchiki::traits::TendrilSink;
 
-    let encoded_version = mc_version_to_curseforge_id(&target_game_version.to_string()).expect("bad game version");
+    let encoded_version = mc_version_to_curseforge_id(&target_game_version.to_string())
+        .context(error::UnknownGameVersion{version: target_game_version.to_string()})?;
     
     let all_url = format!("https://www.curseforge.com/minecraft/mc-mods/{}/files/all?filter-game-version=2020709689:{}&page={}",curse_mod.id,encoded_version,page_num);
 
@@ -285,19 +326,265 @@ async fn page_for_version(
     let doc = kuchiki::parse_html()
         .from_utf8()
         .read_from(&mut Cursor::new(body))
-        .unwrap();
+        .context(error::Io)?;
     let rows = doc.select("table.project-file-listing tbody tr")
         .map_err(|_| crate::Error::Selector)?;
 
     let mut mods = vec![];
 
     for row in rows {
-        let release_status =
-            row.select("td").nth(0).unwrap().select_first("span").text_contents();
-        let files_cell = row.select("td").nth(1).unwrap();
-        let version = parse_files_url(&files_cell.select_first("a").get_attr("href").expect("missing link to file shouldn't be possible"))?;
+        let bad_row = |problem: &'static str| error::BadFileListing{url: all_url.clone(), problem};
+        let mut cells = row.select("td");
+        let release_status = cells.next()
+            .and_then(|cell| cell.select("span").next())
+            .context(bad_row("a file has no release status"))?
+            .text_contents();
+        let release_status = ReleaseStatus::parse_short(&release_status).ok().context(bad_row("a file has an unknown release status"))?;
+        let link = cells.next()
+            .and_then(|cell| cell.select("a").next())
+            .and_then(|link| link.get_attr("href"))
+            .context(bad_row("a file has no link"))?;
+        let version = parse_files_url(&link)?;
 
-        mods.push(ReleaseInfo{release_status: ReleaseStatus::parse_short(&release_status).expect("Bad release status"),modd:curseforge::Mod{version,id: curse_mod.id.clone()}});
+        mods.push(ReleaseInfo{release_status,modd:curseforge::Mod{version,id: curse_mod.id.clone()}});
     }
     Ok(mods)
-}
\ No newline at end of file
+}
+///Finds the slug of a project, following curseforge's redirects for numeric ids
+pub async fn resolve_project(
+    project: curseforge::ProjectRef,
+    http_client: HttpSimple,
+) -> Result<String,crate::Error> {
+    match project {
+        curseforge::ProjectRef::Slug(slug) => Ok(slug),
+        curseforge::ProjectRef::Id(_) => {
+            let uri = Uri::from_str(&project.redirecting_url()).context(error::Uri)?;
+            let (_res,url) = http_client.get_following_redirects(uri).context(error::Download)?.await.context(error::Download)?;
+            curseforge::parse_modid_from_url(url.as_str())
+        }
+    }
+}
+
+///Fetches the changelog the author wrote for a single file, as plain text
+pub async fn file_changelog(
+    curse_mod: curseforge::Mod,
+    http_client: HttpSimple,
+) -> Result<String,crate::Error> {
+    let file_url = format!("https://www.curseforge.com/minecraft/mc-mods/{}/files/{}",curse_mod.id,curse_mod.version);
+    let doc = fetch_page(&http_client, &file_url).await?;
+    let changelog = doc.select("div.user-content")
+        .map_err(|_| crate::Error::Selector)?
+        .next()
+        .map(|node| node.text_contents())
+        .unwrap_or_default();
+    Ok(changelog.trim().to_owned())
+}
+
+fn parse_page(body: &[u8]) -> kuchiki::NodeRef {
+    use std::io::Cursor;
+    use kuchiki::traits::TendrilSink;
+
+    kuchiki::parse_html()
+        .from_utf8()
+        .read_from(&mut Cursor::new(body))
+        .unwrap()
+}
+
+async fn fetch_page(http_client: &HttpSimple, url: &str) -> Result<kuchiki::NodeRef,crate::Error> {
+    let body = http_client.get(Uri::from_str(url).context(error::Uri)?)
+            .await
+            .context(error::Http)?
+            .into_body()
+            .map_ok(hyper::Chunk::into_bytes)
+            .try_concat()
+            .await
+            .context(error::Http)?;
+    Ok(parse_page(&body))
+}
+
+///The notice curseforge shows on an archived project's page.
+///Other parts of a live page, such as the filter for archived files, also have "archived" in their classes
+const ARCHIVED_NOTICE: &str = ".project-archived-notice";
+
+///Curseforge keeps archived projects up with a notice on the page, rather than any status code
+fn is_archived(doc: &kuchiki::NodeRef) -> bool {
+    doc.select(ARCHIVED_NOTICE)
+        .map(|mut elements| elements.next().is_some())
+        .unwrap_or(false)
+}
+
+///Finds the slug the project `id` lives at now, which differs from `id` if the project was renamed.
+///Fails with `ProjectDeleted` or `ProjectArchived` if the project is gone.
+pub async fn current_slug(
+    id: String,
+    http_client: HttpSimple,
+) -> Result<String,crate::Error> {
+    let uri = Uri::from_str(&curseforge::ProjectRef::Slug(id.clone()).redirecting_url()).context(error::Uri)?;
+    let (res,url) = http_client.get_following_redirects(uri).context(error::Download)?.await.context(error::Download)?;
+    if res.status() == http::StatusCode::NOT_FOUND {
+        return error::ProjectDeleted{id}.fail();
+    }
+    let slug = match curseforge::parse_modid_from_url(url.as_str()) {
+        Ok(slug) => slug,
+        Err(_) => return error::UnexpectedRedirect{id, url: url.to_string()}.fail(),
+    };
+    let body = res.into_body()
+        .map_ok(hyper::Chunk::into_bytes)
+        .try_concat()
+        .await
+        .context(error::Http)?;
+    if is_archived(&parse_page(&body)) {
+        return error::ProjectArchived{id}.fail();
+    }
+    Ok(slug)
+}
+
+///The ids of projects which list `mod_id` as a required dependency
+pub async fn required_by(
+    mod_id: String,
+    http_client: HttpSimple,
+) -> Result<Vec<String>,crate::Error> {
+    let dependents_url = format!("https://www.curseforge.com/minecraft/mc-mods/{}/relations/dependents?filter-related-dependents=3",mod_id);
+    let doc = fetch_page(&http_client, &dependents_url).await?;
+    let links = doc.select("ul.listing a[href^=\"/minecraft/mc-mods/\"]")
+        .map_err(|_| crate::Error::Selector)?;
+
+    let mut ids: Vec<String> = links
+        .filter_map(|link| link.get_attr("href"))
+        .filter_map(|href| curseforge::parse_modid_from_url(&format!("https://www.curseforge.com{}", href)).ok())
+        .filter(|id| *id != mod_id)
+        .collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+///The release status of a single file
+pub async fn file_release_status(
+    curse_mod: curseforge::Mod,
+    http_client: HttpSimple,
+) -> Result<Option<ReleaseStatus>,crate::Error> {
+    let file_url = format!("https://www.curseforge.com/minecraft/mc-mods/{}/files/{}",curse_mod.id,curse_mod.version);
+    let doc = fetch_page(&http_client, &file_url).await?;
+    let status = doc.select("span[class*=\"file-phase--\"]")
+        .map_err(|_| crate::Error::Selector)?
+        .next()
+        .map(|node| node.text_contents());
+    Ok(status.and_then(|status| {
+        let status = status.trim();
+        ReleaseStatus::parse_short(status).or_else(|_| ReleaseStatus::from_str(status)).ok()
+    }))
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult{
+    pub id: String,
+    pub name: String,
+    pub summary: String,
+    pub downloads: Option<u64>,
+    pub game_versions: Vec<String>,
+}
+
+impl SearchResult{
+    ///Whether any of the listed game versions (like `1.14` or `1.14.4`) meets `version`
+    pub fn supports(&self, version: &semver::VersionReq) -> bool {
+        self.game_versions.iter().any(|game_version| {
+            let padded = if game_version.matches('.').count() == 1 { format!("{}.0", game_version) } else { game_version.clone() };
+            semver::Version::parse(&padded).map(|game_version| version.matches(&game_version)).unwrap_or(false)
+        })
+    }
+}
+
+///Parses curseforge's abbreviated download counts, like `1.2M Downloads`
+fn parse_download_count(s: &str) -> Option<u64> {
+    let count = s.trim().trim_end_matches("Downloads").trim().replace(',', "");
+    let (number, multiplier) = match count.chars().last()? {
+        'K' | 'k' => (&count[..count.len() - 1], 1_000.0),
+        'M' | 'm' => (&count[..count.len() - 1], 1_000_000.0),
+        'B' | 'b' => (&count[..count.len() - 1], 1_000_000_000.0),
+        _ => (count.as_str(), 1.0),
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    Some((number * multiplier).round() as u64)
+}
+
+///Searches curseforge for mods matching `query`, in curseforge's own order
+pub async fn search(
+    query: String,
+    http_client: HttpSimple,
+) -> Result<Vec<SearchResult>,crate::Error> {
+    let encoded: String = url::form_urlencoded::byte_serialize(query.as_bytes()).collect();
+    let search_url = format!("https://www.curseforge.com/minecraft/mc-mods/search?search={}",encoded);
+    let doc = fetch_page(&http_client, &search_url).await?;
+    let rows = doc.select("div.project-listing-row")
+        .map_err(|_| crate::Error::Selector)?;
+
+    let mut results = vec![];
+    for row in rows {
+        let link = match row.select("a[href^=\"/minecraft/mc-mods/\"]").next() {
+            Some(link) => link,
+            None => continue,
+        };
+        let id = match link.get_attr("href").and_then(|href| curseforge::parse_modid_from_url(&format!("https://www.curseforge.com{}", href)).ok()) {
+            Some(id) => id,
+            None => continue,
+        };
+        let name = row.select("h3").next().map(|node| node.text_contents().trim().to_owned()).unwrap_or_else(|| id.clone());
+        let summary = row.select("p").next().map(|node| node.text_contents().trim().to_owned()).unwrap_or_default();
+        let downloads = row.select("span")
+            .map(|node| node.text_contents())
+            .find(|text| text.trim().ends_with("Downloads"))
+            .and_then(|text| parse_download_count(&text));
+        let game_versions = row.select("span")
+            .map(|node| node.text_contents())
+            .find(|text| text.contains("Game Version:"))
+            .map(|text| text.replace("Game Version:", "").split(',').map(|version| version.trim().to_owned()).filter(|version| !version.is_empty()).collect())
+            .unwrap_or_default();
+        results.push(SearchResult{id, name, summary, downloads, game_versions});
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_download_counts() {
+        assert_eq!(parse_download_count("1.2M Downloads"), Some(1_200_000));
+        assert_eq!(parse_download_count("35.8K Downloads"), Some(35_800));
+        assert_eq!(parse_download_count("512 Downloads"), Some(512));
+        assert_eq!(parse_download_count("1,024 Downloads"), Some(1_024));
+        assert_eq!(parse_download_count("Downloads"), None);
+    }
+
+    #[test]
+    fn spots_archived_projects() {
+        assert!(is_archived(&parse_page(b"<html><body><div class=\"alert project-archived-notice\">This project is archived</div></body></html>")));
+        assert!(!is_archived(&parse_page(b"<html><body><h2>Just Enough Items</h2></body></html>")));
+    }
+
+    #[test]
+    fn live_projects_with_archived_classes_are_not_archived() {
+        let live = b"<html><body><h2>Just Enough Items</h2>\
+            <label class=\"filter-archived-toggle\"><input type=\"checkbox\" class=\"show-archived\">Show archived files</label>\
+            <p>Older versions are in the archived files section.</p></body></html>";
+        assert!(!is_archived(&parse_page(live)));
+    }
+
+    #[test]
+    fn matches_listed_game_versions() {
+        let result = |versions: &[&str]| SearchResult{
+            id: "jei".into(),
+            name: "Just Enough Items".into(),
+            summary: String::new(),
+            downloads: None,
+            game_versions: versions.iter().map(|version| version.to_string()).collect(),
+        };
+        let req = semver::VersionReq::parse("~1.14.4").unwrap();
+        assert!(result(&["1.12.2", "1.14.4"]).supports(&req));
+        assert!(!result(&["1.14"]).supports(&req));
+        assert!(result(&["1.14"]).supports(&semver::VersionReq::parse("~1.14").unwrap()));
+        assert!(!result(&["Forge"]).supports(&req));
+    }
+}
@@ -0,0 +1,204 @@
+//! Classifies the many shapes of curseforge link people paste into packs
+use std::str::FromStr;
+
+///How a link names a project: by its slug, or by its numeric id (which needs a redirect to find the slug)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectRef {
+    Slug(String),
+    Id(u64),
+}
+
+impl ProjectRef {
+    ///A link to the project that curseforge redirects to its canonical url
+    pub fn redirecting_url(&self) -> String {
+        match *self {
+            ProjectRef::Slug(ref slug) => format!("https://www.curseforge.com/minecraft/mc-mods/{}", slug),
+            ProjectRef::Id(id) => format!("https://minecraft.curseforge.com/projects/{}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CurseLink {
+    ///A project page, or anything else about a project that doesn't single out one file
+    Project(ProjectRef),
+    ///A specific file of a project
+    File{
+        project: ProjectRef,
+        file_id: u64,
+    },
+    ///A direct download from the cdn, which only tells us the file
+    CdnFile{
+        file_id: u64,
+        file_name: String,
+    },
+}
+
+impl CurseLink {
+    pub fn project(&self) -> Option<&ProjectRef> {
+        match *self {
+            CurseLink::Project(ref project) | CurseLink::File{ref project, ..} => Some(project),
+            CurseLink::CdnFile{..} => None,
+        }
+    }
+
+    pub fn file_id(&self) -> Option<u64> {
+        match *self {
+            CurseLink::Project(_) => None,
+            CurseLink::File{file_id, ..} | CurseLink::CdnFile{file_id, ..} => Some(file_id),
+        }
+    }
+}
+
+fn project_ref(segment: &str) -> Option<ProjectRef> {
+    if segment.is_empty() {
+        None
+    } else if let Ok(id) = u64::from_str(segment) {
+        Some(ProjectRef::Id(id))
+    } else {
+        Some(ProjectRef::Slug(segment.to_owned()))
+    }
+}
+
+///`<project>[/files|/download/<file id>[/...]]`
+fn project_link(segments: &[&str]) -> Option<CurseLink> {
+    let project = project_ref(segments.first()?)?;
+    match segments.get(1..3) {
+        Some(&[kind, file_id]) if kind == "files" || kind == "download" => match u64::from_str(file_id) {
+            Ok(file_id) => Some(CurseLink::File{ project, file_id }),
+            //e.g. /files/all
+            Err(_) => Some(CurseLink::Project(project)),
+        },
+        _ => Some(CurseLink::Project(project)),
+    }
+}
+
+impl FromStr for CurseLink {
+    type Err = crate::Error;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let bad_url = || crate::Error::BadModUrl{ url: url.to_owned() };
+        let trimmed = url.trim();
+
+        if let Ok(id) = u64::from_str(trimmed) {
+            return Ok(CurseLink::Project(ProjectRef::Id(id)));
+        }
+
+        let without_scheme = trimmed
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        let without_query = without_scheme
+            .split(|c| c == '?' || c == '#')
+            .next()
+            .unwrap_or("");
+        let mut parts = without_query.split('/').filter(|part| !part.is_empty());
+        let host = parts.next().ok_or_else(bad_url)?.to_lowercase();
+        let segments: Vec<&str> = parts.collect();
+
+        let on_curseforge = host == "www.curseforge.com" || host == "curseforge.com";
+        let link = if on_curseforge && segments.starts_with(&["minecraft", "mc-mods"]) {
+            project_link(&segments[2..])
+        } else if (on_curseforge || host == "minecraft.curseforge.com") && segments.starts_with(&["projects"]) {
+            project_link(&segments[1..])
+        } else if host.ends_with("forgecdn.net") {
+            match segments.as_slice() {
+                //the file id is split in two, with the low part zero padded
+                &["files", high, low, file_name] => match (u64::from_str(high), u64::from_str(low)) {
+                    (Ok(high), Ok(low)) if low < 1000 => Some(CurseLink::CdnFile{
+                        file_id: high * 1000 + low,
+                        file_name: file_name.to_owned(),
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            }
+        } else {
+            None
+        };
+        link.ok_or_else(bad_url)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn slug(s: &str) -> ProjectRef {
+        ProjectRef::Slug(s.into())
+    }
+
+    fn file(project: ProjectRef, file_id: u64) -> CurseLink {
+        CurseLink::File{ project, file_id }
+    }
+
+    fn parse(url: &str) -> CurseLink {
+        url.parse().unwrap_or_else(|e| panic!("{} should parse: {}", url, e))
+    }
+
+    #[test]
+    fn parses_current_file_links() {
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/files/2803400"), file(slug("jei"), 2803400));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/download/2803400"), file(slug("jei"), 2803400));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/download/2803400/file"), file(slug("jei"), 2803400));
+        assert_eq!(parse("http://www.curseforge.com/minecraft/mc-mods/jei/files/2803400"), file(slug("jei"), 2803400));
+        assert_eq!(parse("https://curseforge.com/minecraft/mc-mods/jei/files/2803400/"), file(slug("jei"), 2803400));
+        assert_eq!(parse("www.curseforge.com/minecraft/mc-mods/jei/files/2803400"), file(slug("jei"), 2803400));
+    }
+
+    #[test]
+    fn parses_project_links() {
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei"), CurseLink::Project(slug("jei")));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/"), CurseLink::Project(slug("jei")));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/files"), CurseLink::Project(slug("jei")));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/files/all?filter-game-version=2020709689:6756"), CurseLink::Project(slug("jei")));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/relations/dependents"), CurseLink::Project(slug("jei")));
+    }
+
+    #[test]
+    fn ignores_queries_and_fragments() {
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei/files/2803400?utm_source=discord"), file(slug("jei"), 2803400));
+        assert_eq!(parse("https://www.curseforge.com/minecraft/mc-mods/jei#c12"), CurseLink::Project(slug("jei")));
+    }
+
+    #[test]
+    fn parses_legacy_links() {
+        assert_eq!(parse("https://minecraft.curseforge.com/projects/jei"), CurseLink::Project(slug("jei")));
+        assert_eq!(parse("https://minecraft.curseforge.com/projects/jei/files/2803400"), file(slug("jei"), 2803400));
+        assert_eq!(parse("https://minecraft.curseforge.com/projects/jei/files/2803400/download"), file(slug("jei"), 2803400));
+        assert_eq!(parse("http://minecraft.curseforge.com/projects/238222"), CurseLink::Project(ProjectRef::Id(238222)));
+        assert_eq!(parse("https://www.curseforge.com/projects/238222"), CurseLink::Project(ProjectRef::Id(238222)));
+    }
+
+    #[test]
+    fn parses_numeric_ids() {
+        assert_eq!(parse("238222"), CurseLink::Project(ProjectRef::Id(238222)));
+        assert_eq!(parse(" 238222\n"), CurseLink::Project(ProjectRef::Id(238222)));
+    }
+
+    #[test]
+    fn parses_cdn_links() {
+        assert_eq!(
+            parse("https://media.forgecdn.net/files/2803/400/jei_1.12.2-4.15.0.293.jar"),
+            CurseLink::CdnFile{ file_id: 2803400, file_name: "jei_1.12.2-4.15.0.293.jar".into() }
+        );
+        assert_eq!(
+            parse("https://edge.forgecdn.net/files/2724/20/Baubles-1.12-1.5.2.jar"),
+            CurseLink::CdnFile{ file_id: 2724020, file_name: "Baubles-1.12-1.5.2.jar".into() }
+        );
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        for url in &[
+            "",
+            "https://",
+            "https://www.curseforge.com/",
+            "https://www.curseforge.com/minecraft/modpacks",
+            "https://github.com/mezz/JustEnoughItems",
+            "https://media.forgecdn.net/files/2803/jei.jar",
+            "jei",
+        ] {
+            assert!(url.parse::<CurseLink>().is_err(), "{} should not parse", url);
+        }
+    }
+}
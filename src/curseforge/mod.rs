@@ -1,32 +1,28 @@
 use http::{self, Uri};
 use std::path::PathBuf;
 use std::str::FromStr;
+use schemars::JsonSchema;
 use crate::{
     error::prelude::*,
 };
 
 pub mod api;
+pub mod link;
+pub use link::{CurseLink, ProjectRef};
 mod release_status;
 pub use release_status::*;
 
+///The slug of the project `url` links to, for any link that names the project by slug
 pub fn parse_modid_from_url(url: &str) -> Result<String,crate::Error>{
-    use nom::bytes::complete::*;
-
-    fn error<'a, I>(url: &'a str) -> impl (Fn(nom::Err<(I,nom::error::ErrorKind)>) -> crate::Error) + 'a{
-        move |_|{
-            crate::Error::BadModUrl {
-                url: url.to_owned(),
-            }
-        }
+    match url.parse::<CurseLink>()?.project() {
+        Some(ProjectRef::Slug(slug)) => Ok(slug.clone()),
+        _ => Err(crate::Error::BadModUrl{
+            url: url.to_owned(),
+        }),
     }
-
-    let (rest,_tag) = tag("https://www.curseforge.com/minecraft/mc-mods/")(url).map_err(error(url))?;
-    let (_rest, id) = take_till(|c: char| c == '/')(rest).map_err(error(url))?;
-
-    Ok(id.to_string())
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq, JsonSchema)]
 pub struct Mod {
     pub id: String,
     pub version: u64,
@@ -45,29 +41,17 @@ impl Mod {
         Ok(Uri::from_str(&loc)?)
     }
 
+    ///The file `url` links to, for any link that names both the project (by slug) and the file
     pub fn from_url(url: &str) -> crate::Result<Self>{
-        use nom::bytes::complete::*;
-        use nom::branch::*;
-        use nom::combinator::*;
-
-        fn error<'a, I>(url: &'a str) -> impl (Fn(nom::Err<(I,nom::error::ErrorKind)>) -> crate::Error) + 'a{
-            move |_|{
-                crate::Error::BadModUrl {
-                    url: url.to_owned(),
-                }
-            }
+        match url.parse::<CurseLink>()? {
+            CurseLink::File{project: ProjectRef::Slug(id), file_id} => Ok(Self{
+                id,
+                version: file_id,
+            }),
+            _ => Err(crate::Error::BadModUrl {
+                url: url.to_owned(),
+            }),
         }
-
-        let (rest,_tag) = tag("https://www.curseforge.com/minecraft/mc-mods/")(url).map_err(error(url))?;
-        let (rest,id) = take_till(|c: char| c == '/')(rest).map_err(error(url))?;
-        let (rest,_tag) = alt((tag("/download/"),tag("/files/")))(rest).map_err(error(url))?;
-        let (rest,version) = map_res(take_while(|c: char| c.is_digit(10)), u64::from_str)(rest).map_err(error(url))?;
-        let (_rest,_tag) = opt(tag("/file"))(rest).map_err(error(url))?;
-
-        Ok(Self{
-            id: id.to_owned(),
-            version,
-        })
     }
 }
 
@@ -1,7 +1,8 @@
 use serde::{Deserialize,Serialize};
+use schemars::JsonSchema;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub enum ReleaseStatus {
     Release,
     Beta,
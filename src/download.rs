@@ -17,10 +17,12 @@ use slog::Logger;
 use url;
 use crate::{
     util,
+    maven::RepositoryConfig,
     error::prelude::*,
 };
 use std::{
     path::PathBuf,
+    sync::Arc,
     task::{Poll,Context},
     pin::Pin,
 };
@@ -64,6 +66,15 @@ pub mod error{
         },
         #[snafu(display("A http server error occurred. Please try again later"))]
         HttpServer,
+        #[snafu(display("{} is not in the local cache, and can't be downloaded in offline mode", uri))]
+        Offline{
+            uri: http::Uri,
+        },
+        #[snafu(display("Maven error: {}", source))]
+        Maven{
+            #[snafu(source(from(crate::maven::Error, Box::new)))]
+            source: Box<crate::maven::Error>,
+        },
         #[snafu(display("Cache error: {}", source))]
         Cached{
             #[snafu(source(from(crate::cache::Error, Box::new)))]
@@ -170,6 +181,8 @@ impl HttpSimple {
 #[derive(Default, Clone)]
 pub struct Manager {
     http_client: HttpSimple,
+    offline: bool,
+    repositories: Option<Arc<RepositoryConfig>>,
 }
 
 impl Manager {
@@ -177,7 +190,38 @@ impl Manager {
         Self::default()
     }
 
+    ///When offline, nothing will be fetched from the network, only files which already exist are used
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self{
+            offline,
+            ..self
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    ///Sets the maven repositories artifacts downloaded through this manager are resolved on
+    pub fn with_repositories(self, repositories: RepositoryConfig) -> Self {
+        Self{
+            repositories: Some(Arc::new(repositories)),
+            ..self
+        }
+    }
+
+    ///The maven repositories to resolve artifacts on, the global config is used if none were set
+    pub fn repositories(&self) -> &RepositoryConfig {
+        match self.repositories {
+            Some(ref repositories) => repositories,
+            None => RepositoryConfig::global(),
+        }
+    }
+
     pub fn get(&self, url: hyper::Uri) -> Result<RedirectFollower> {
+        if self.offline {
+            return error::Offline{uri: url}.fail();
+        }
         self.http_client
             .request_following_redirects(self.request_with_base_headers(http::Method::GET, url))
     }
@@ -240,10 +284,21 @@ impl Manager {
             path.with_file_name("")
         };
 
-        let mut request = self.request_with_base_headers(http::Method::GET, uri);
+        let mut request = self.request_with_base_headers(http::Method::GET, uri.clone());
         let http_client = self.http_client.clone();
+        let offline = self.offline;
 
         async move{
+            if offline {
+                //no conditional requests in offline mode, an existing file is always good enough
+                return if !append_filename && path.is_file() {
+                    trace!(log, "offline, using existing file {}", path.as_path().to_string_lossy());
+                    Ok(())
+                } else {
+                    error::Offline{uri}.fail()
+                };
+            }
+
             trace!(log,"Creating dir {}",folder_path.to_string_lossy());
             tokio::fs::create_dir_all(folder_path).await.context(error::Io)?;
 
@@ -28,6 +28,10 @@ pub enum Error{
     Download{
         source: crate::download::Error
     },
+    #[snafu(display("Maven error {}", source))]
+    Maven{
+        source: crate::maven::Error,
+    },
     #[snafu(display("Console io error {}", source))]
     Console{
         source: std::io::Error
@@ -54,6 +58,32 @@ pub enum Error{
     BadModUrl{
         url: String,
     },
+    #[snafu(display("Curseforge project {} no longer exists", id))]
+    ProjectDeleted{
+        id: String,
+    },
+    #[snafu(display("Curseforge project {} has been archived", id))]
+    ProjectArchived{
+        id: String,
+    },
+    #[snafu(display("Curseforge project {} redirected to unexpected page {}", id, url))]
+    UnexpectedRedirect{
+        id: String,
+        url: String,
+    },
+    #[snafu(display("Curseforge has no id for minecraft version {}", version))]
+    UnknownGameVersion{
+        version: String,
+    },
+    #[snafu(display("Unexpected curseforge file listing at {}: {}", url, problem))]
+    BadFileListing{
+        url: String,
+        problem: String,
+    },
+    #[snafu(display("The following items are not in the local cache:\n\t{}", missing.join("\n\t")))]
+    MissingFromCache{
+        missing: Vec<String>,
+    },
     #[snafu(display("{}",source))]
     Dynamic{
         source: Box<dyn std::error::Error + Send + Sync>,
@@ -9,10 +9,6 @@ use serde_json::{self, Value};
 use std;
 use std::path::Path;
 
-const HACK_REQUIRED: &[& str] = &["com.typesafe.akka", "com.typesafe"];
-
-const HACK_REPO_REDIRECT: & str = "https://repo1.maven.org/maven2/";
-
 #[derive(Debug,Snafu)]
 pub enum Error{
     #[snafu(display("Forge version json couldn't be opened due to: {}", source))]
@@ -32,7 +28,8 @@ pub enum Error{
 
 
 //TODO: use more specific "version json" error here?
-pub fn hack_forge_version_json<P>(path: P) -> Result<(),Error>
+///Points any libraries whose group is routed to a specific repository in `repositories` at that repository
+pub fn hack_forge_version_json<P>(path: P, repositories: &maven::RepositoryConfig) -> Result<(),Error>
     where P: AsRef<Path>
 {
     let path = path.as_ref();
@@ -55,8 +52,8 @@ pub fn hack_forge_version_json<P>(path: P) -> Result<(),Error>
                 .expect("library name was not a string")
                 .parse()
                 .expect("library name was not a maven identifier");
-            if HACK_REQUIRED.contains(&artifact.group.as_str()) {
-                library.insert("url".to_string(), serde_json::to_value(HACK_REPO_REDIRECT).expect("Couldn't convert pre-checked const to json value"));
+            if let Some(repo) = repositories.routed_repo(&artifact.group) {
+                library.insert("url".to_string(), Value::String(repo.to_string()));
             }
         }
     }
@@ -0,0 +1,234 @@
+//! What we last installed into a pack folder, so later updates can report what changed
+use crate::{
+    maven::hash_writer::{HashAlgorithm, HashWriter},
+    mod_source::ModpackConfig,
+    optional_mods::Choices,
+    error::prelude::*,
+};
+use snafu::Snafu;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+pub const STATE_DIR: &str = ".modpack_tool";
+const STATE_FILE: &str = "state.json";
+///The folder mods are installed into, relative to the pack folder
+pub const MODS_DIR: &str = "mods";
+
+///Installed files, by path relative to the pack folder, with their sha256 hashes
+pub type Files = BTreeMap<String, String>;
+
+#[derive(Debug,Snafu)]
+pub enum Error{
+    #[snafu(display("Io error {} while reading install state {}", source, path))]
+    Reading{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Io error {} while writing install state {}", source, path))]
+    Writing{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Io error {} while hashing installed file {}", source, path))]
+    Hashing{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Install state {} is in bad format: {}", path, source))]
+    BadState{
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct InstallState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_version: Option<semver::Version>,
+    ///Which optional mods were chosen, so later updates keep them
+    #[serde(default, skip_serializing_if = "Choices::is_empty")]
+    pub choices: Choices,
+    ///The hash of the pack as installed, from `hash_pack`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_hash: Option<String>,
+    ///The files we installed, so updates and uninstalls only touch those, and can tell when they've been changed
+    #[serde(default, skip_serializing_if = "Files::is_empty")]
+    pub files: Files,
+    ///The key of the profile we added to the launcher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_key: Option<String>,
+    ///The forge version id the launcher profile runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge_version_id: Option<String>,
+    ///The exact version of each maven mod installed, by mod key, since the pack may only give a range or `LATEST`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub maven_versions: BTreeMap<String, String>,
+}
+
+///How an installed file differs from what we installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange{
+    Modified(String),
+    Missing(String),
+    ///A file we didn't install, such as a mod the player added themselves
+    Untracked(String),
+}
+
+impl std::fmt::Display for FileChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FileChange::Modified(name) => write!(f, "modified:  {}", name),
+            FileChange::Missing(name) => write!(f, "missing:   {}", name),
+            FileChange::Untracked(name) => write!(f, "untracked: {}", name),
+        }
+    }
+}
+
+///Hashes the pack as resolved, to tell whether it has changed since it was installed
+pub fn hash_pack(pack: &ModpackConfig) -> String {
+    let mut hash = HashWriter::with_algorithm(HashAlgorithm::Sha256);
+    let contents = serde_json::to_vec(pack).expect("packs always serialize");
+    hash.write_all(&contents).expect("HashWriter can't fail");
+    hash.digest()
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hash = HashWriter::with_algorithm(HashAlgorithm::Sha256);
+    std::io::copy(&mut file, &mut hash)?;
+    Ok(hash.digest())
+}
+
+///Whether `name` is a file directly inside `dir`, as `scan` names them, so it can't point outside the pack folder
+pub fn is_file_in(name: &str, dir: &str) -> bool {
+    let mut parts = name.splitn(2, '/');
+    if parts.next() != Some(dir) {
+        return false;
+    }
+    let file = match parts.next() {
+        Some(file) => file,
+        None => return false,
+    };
+    let mut components = Path::new(file).components();
+    !file.contains('\\')
+        && match (components.next(), components.next()) {
+            (Some(std::path::Component::Normal(_)), None) => true,
+            _ => false,
+        }
+}
+
+///Hashes the files directly inside `dir` of the pack folder, or none if it doesn't exist
+pub fn scan(pack_path: &Path, dir: &str) -> Result<Files, Error> {
+    let dir_path = pack_path.join(dir);
+    let entries = match std::fs::read_dir(&dir_path) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Files::new()),
+        Err(e) => return Err(Reading{path: dir_path.display().to_string()}.into_error(e)),
+    };
+    let mut files = Files::new();
+    for entry in entries {
+        let entry = entry.context(Reading{path: dir_path.display().to_string()})?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let hash = hash_file(&path).context(Hashing{path: path.display().to_string()})?;
+        files.insert(format!("{}/{}", dir, entry.file_name().to_string_lossy()), hash);
+    }
+    Ok(files)
+}
+
+impl InstallState {
+    pub fn path(pack_path: &Path) -> PathBuf {
+        pack_path.join(STATE_DIR).join(STATE_FILE)
+    }
+
+    ///Loads the state for the pack installed at `pack_path`, or None if nothing has been installed there yet
+    pub fn load(pack_path: &Path) -> Result<Option<Self>, Error> {
+        let path = Self::path(pack_path);
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Reading{path: path.display().to_string()}.into_error(e)),
+        };
+        serde_json::from_slice(&contents).map(Some).context(BadState{path: path.display().to_string()})
+    }
+
+    pub fn save(&self, pack_path: &Path) -> Result<(), Error> {
+        let path = Self::path(pack_path);
+        let dir = pack_path.join(STATE_DIR);
+        std::fs::create_dir_all(&dir).context(Writing{path: dir.display().to_string()})?;
+        let contents = serde_json::to_vec_pretty(self).context(BadState{path: path.display().to_string()})?;
+        std::fs::write(&path, contents).context(Writing{path: path.display().to_string()})
+    }
+
+    ///Removes the state, for when the pack is uninstalled
+    pub fn remove(pack_path: &Path) -> Result<(), Error> {
+        let dir = pack_path.join(STATE_DIR);
+        match std::fs::remove_dir_all(&dir) {
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result.context(Writing{path: dir.display().to_string()}),
+        }
+    }
+
+    ///How the `current` files, as from `scan`, differ from the ones we installed
+    pub fn changes(&self, current: &Files) -> Vec<FileChange> {
+        let mut changes = vec![];
+        for (name, hash) in &self.files {
+            match current.get(name) {
+                Some(current_hash) if current_hash != hash => changes.push(FileChange::Modified(name.clone())),
+                Some(_) => {}
+                None => changes.push(FileChange::Missing(name.clone())),
+            }
+        }
+        changes.extend(current.keys()
+            .filter(|name| !self.files.contains_key(*name))
+            .map(|name| FileChange::Untracked(name.clone())));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn files(files: &[(&str, &str)]) -> Files {
+        files.iter().map(|&(name, hash)| (name.to_owned(), hash.to_owned())).collect()
+    }
+
+    #[test]
+    fn finds_modified_missing_and_untracked_files() {
+        let state = InstallState{
+            files: files(&[("mods/jei.jar", "aa"), ("mods/optifine.jar", "bb"), ("mods/journeymap.jar", "cc")]),
+            ..InstallState::default()
+        };
+        let current = files(&[("mods/jei.jar", "aa"), ("mods/optifine.jar", "b2"), ("mods/minimap.jar", "dd")]);
+        assert_eq!(state.changes(&current), vec![
+            FileChange::Missing("mods/journeymap.jar".into()),
+            FileChange::Modified("mods/optifine.jar".into()),
+            FileChange::Untracked("mods/minimap.jar".into()),
+        ]);
+    }
+
+    #[test]
+    fn only_files_directly_in_the_dir_are_in_it() {
+        assert!(is_file_in("mods/jei.jar", MODS_DIR));
+        assert!(!is_file_in("mods/../options.txt", MODS_DIR));
+        assert!(!is_file_in("mods/sub/jei.jar", MODS_DIR));
+        assert!(!is_file_in("mods/..", MODS_DIR));
+        assert!(!is_file_in("mods/", MODS_DIR));
+        assert!(!is_file_in("mods//etc/passwd", MODS_DIR));
+        assert!(!is_file_in("mods/..\\options.txt", MODS_DIR));
+        assert!(!is_file_in("saves/world.dat", MODS_DIR));
+    }
+
+    #[test]
+    fn reads_states_from_before_files_were_tracked() {
+        let state: InstallState = serde_json::from_str(r#"{"pack_version": "1.2.0"}"#).unwrap();
+        assert!(state.files.is_empty());
+        assert_eq!(state.changes(&files(&[("mods/jei.jar", "aa")])), vec![FileChange::Untracked("mods/jei.jar".into())]);
+    }
+}
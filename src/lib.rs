@@ -11,6 +11,8 @@ extern crate slog;
 #[macro_use]
 extern crate lazy_static;
 
+#[macro_use]
+pub mod prompt;
 pub mod cache;
 pub mod curseforge;
 pub mod download;
@@ -23,6 +25,16 @@ pub mod cmds;
 pub mod async_json;
 pub mod mc_libs;
 pub mod error;
+pub mod bundle;
+pub mod install_state;
+pub mod pack_diff;
+pub mod pack_merge;
+pub mod pack_edit;
+pub mod upgrade_report;
+pub mod pack_validate;
+pub mod pack_format;
+pub mod pack_overlay;
+pub mod optional_mods;
 
 pub use download::Downloadable;
 
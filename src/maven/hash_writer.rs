@@ -1,7 +1,72 @@
-use sha1::{Digest, Sha1};
+use sha1::Sha1;
+use sha2::{Digest as _, Sha256, Sha512};
 use std::io::{Result, Write};
 
-pub struct HashWriter(Sha1);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha512,
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl HashAlgorithm {
+    ///Every supported algorithm, strongest first
+    pub const ALL: [Self; 4] = [Self::Sha512, Self::Sha256, Self::Sha1, Self::Md5];
+
+    ///The extension maven repositories use for checksum files of this type
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Sha512 => "sha512",
+            Self::Sha256 => "sha256",
+            Self::Sha1 => "sha1",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn hex_len(self) -> usize {
+        match self {
+            Self::Sha512 => 128,
+            Self::Sha256 => 64,
+            Self::Sha1 => 40,
+            Self::Md5 => 32,
+        }
+    }
+
+    ///Extracts the hash from the contents of a checksum file.
+    ///
+    ///Handles bare hashes, `hash  filename` (as written by sha1sum and friends) and `SHA1(filename)= hash` (as written by openssl).
+    pub fn parse_checksum_file(self, contents: &str) -> Option<String> {
+        let contents = contents.trim();
+        let contents = match contents.rfind("= ") {
+            Some(idx) => &contents[idx + 2..],
+            None => contents,
+        };
+        let hash = contents.split_whitespace().next()?.to_lowercase();
+        if hash.len() == self.hex_len() && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some(hash)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+#[derive(Clone)]
+enum Hasher {
+    Sha512(Sha512),
+    Sha256(Sha256),
+    Sha1(Sha1),
+    Md5(md5::Context),
+}
+
+#[derive(Clone)]
+pub struct HashWriter(Hasher);
 
 impl Default for HashWriter {
     fn default() -> Self {
@@ -11,16 +76,46 @@ impl Default for HashWriter {
 
 impl HashWriter {
     pub fn new() -> Self {
-        Self(Sha1::new())
+        Self::with_algorithm(HashAlgorithm::Sha1)
+    }
+    pub fn with_algorithm(algorithm: HashAlgorithm) -> Self {
+        Self(match algorithm {
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Context::new()),
+        })
+    }
+    pub fn algorithm(&self) -> HashAlgorithm {
+        match self.0 {
+            Hasher::Sha512(_) => HashAlgorithm::Sha512,
+            Hasher::Sha256(_) => HashAlgorithm::Sha256,
+            Hasher::Sha1(_) => HashAlgorithm::Sha1,
+            Hasher::Md5(_) => HashAlgorithm::Md5,
+        }
     }
-    pub fn digest(&self) -> Digest {
-        self.0.digest()
+    fn update(&mut self, buf: &[u8]) {
+        match self.0 {
+            Hasher::Sha512(ref mut hasher) => hasher.input(buf),
+            Hasher::Sha256(ref mut hasher) => hasher.input(buf),
+            Hasher::Sha1(ref mut hasher) => hasher.update(buf),
+            Hasher::Md5(ref mut hasher) => hasher.consume(buf),
+        }
+    }
+    ///The lowercase hex digest of everything written so far
+    pub fn digest(&self) -> String {
+        match self.0 {
+            Hasher::Sha512(ref hasher) => format!("{:x}", hasher.clone().result()),
+            Hasher::Sha256(ref hasher) => format!("{:x}", hasher.clone().result()),
+            Hasher::Sha1(ref hasher) => hasher.digest().to_string(),
+            Hasher::Md5(ref hasher) => format!("{:x}", hasher.clone().compute()),
+        }
     }
 }
 
 impl Write for HashWriter {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        self.0.update(buf);
+        self.update(buf);
         Ok(buf.len())
     }
     // can't be flushed so just pretend we did
@@ -36,26 +131,59 @@ use std::{
 
 impl tokio::io::AsyncWrite for HashWriter{
     fn poll_write(
-        mut self: Pin<&mut Self>, 
-        _cx: &mut Context, 
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context,
         buf: &[u8]
     ) -> Poll<tokio::io::Result<usize>>{
-        self.as_mut().0.update(buf);
+        self.as_mut().update(buf);
         Poll::Ready(Ok(buf.len()))
     }
 
     fn poll_flush(
-        self: Pin<&mut Self>, 
+        self: Pin<&mut Self>,
         _cx: &mut Context
     ) -> Poll<tokio::io::Result<()>>{
         Poll::Ready(Ok(()))
     }
 
     fn poll_shutdown(
-        self: Pin<&mut Self>, 
+        self: Pin<&mut Self>,
         _cx: &mut Context
     ) -> Poll<tokio::io::Result<()>>{
         Poll::Ready(Ok(()))
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SHA1_OF_ABC: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+
+    #[test]
+    fn hashes_with_each_algorithm() {
+        let expected = [
+            (HashAlgorithm::Sha512, "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+            (HashAlgorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (HashAlgorithm::Sha1, SHA1_OF_ABC),
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+        ];
+        for &(algorithm, hash) in expected.iter() {
+            let mut writer = HashWriter::with_algorithm(algorithm);
+            writer.write_all(b"abc").unwrap();
+            assert_eq!(writer.digest(), hash, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn parses_checksum_files() {
+        let expected = Some(SHA1_OF_ABC.to_owned());
+        assert_eq!(HashAlgorithm::Sha1.parse_checksum_file(SHA1_OF_ABC), expected);
+        assert_eq!(HashAlgorithm::Sha1.parse_checksum_file(&format!("{}\n", SHA1_OF_ABC)), expected);
+        assert_eq!(HashAlgorithm::Sha1.parse_checksum_file(&format!("{}  forge-1.12.2.jar\n", SHA1_OF_ABC)), expected);
+        assert_eq!(HashAlgorithm::Sha1.parse_checksum_file(&format!("SHA1(forge-1.12.2.jar)= {}", SHA1_OF_ABC.to_uppercase())), expected);
+        assert_eq!(HashAlgorithm::Sha1.parse_checksum_file("<html>not found</html>"), None);
+        assert_eq!(HashAlgorithm::Md5.parse_checksum_file(SHA1_OF_ABC), None);
+    }
+}
@@ -0,0 +1,313 @@
+use regex::Regex;
+use super::version::{self, ComparableVersion, VersionRequest};
+
+fn tag_regex(name: &str) -> Regex {
+    Regex::new(&format!(r"(?s)<{0}>(.*?)</{0}>", regex::escape(name))).expect("tag regex should always be valid")
+}
+
+///The contents of the first `<name>` element in `xml`
+fn first_tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    tag_regex(name)
+        .captures(xml)
+        .and_then(|captures| captures.get(1))
+        .map(|m| m.as_str().trim())
+}
+
+///The contents of every `<name>` element in `xml`
+fn all_tags<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    tag_regex(name)
+        .captures_iter(xml)
+        .filter_map(|captures| captures.get(1))
+        .map(|m| m.as_str().trim())
+        .collect()
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_tag(xml: &mut String, indent: usize, name: &str, value: &str) {
+    xml.push_str(&format!("{}<{1}>{2}</{1}>\n", "  ".repeat(indent), name, escape_xml(value)));
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    pub timestamp: String,
+    pub build_number: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotVersion {
+    pub classifier: Option<String>,
+    pub extension: String,
+    ///The timestamped version, e.g. `1.0-20190612.105713-3`
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Versioning {
+    pub latest: Option<String>,
+    pub release: Option<String>,
+    pub versions: Vec<String>,
+    pub last_updated: Option<String>,
+    pub snapshot: Option<Snapshot>,
+    pub snapshot_versions: Vec<SnapshotVersion>,
+}
+
+///A `maven-metadata.xml` file, either for an artifact (listing its versions) or for a single snapshot version
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub group: Option<String>,
+    pub artifact: Option<String>,
+    pub version: Option<String>,
+    pub versioning: Versioning,
+}
+
+impl Metadata {
+    ///Parses the parts of a maven-metadata.xml we care about, ignoring anything else
+    pub fn parse(xml: &str) -> Self {
+        let versioning_xml = first_tag(xml, "versioning").unwrap_or("");
+        //the top level version is the only one outside of <versioning>, so strip that before looking for it
+        let outer_xml = xml.replace(versioning_xml, "");
+
+        let snapshot = first_tag(versioning_xml, "snapshot").map(|snapshot| Snapshot {
+            timestamp: first_tag(snapshot, "timestamp").unwrap_or("").to_owned(),
+            build_number: first_tag(snapshot, "buildNumber").and_then(|n| n.parse().ok()).unwrap_or(0),
+        });
+
+        let snapshot_versions = all_tags(versioning_xml, "snapshotVersion").into_iter()
+            .filter_map(|snapshot_version| {
+                Some(SnapshotVersion {
+                    classifier: first_tag(snapshot_version, "classifier").map(str::to_owned),
+                    extension: first_tag(snapshot_version, "extension")?.to_owned(),
+                    value: first_tag(snapshot_version, "value")?.to_owned(),
+                })
+            })
+            .collect();
+
+        Self {
+            group: first_tag(&outer_xml, "groupId").map(str::to_owned),
+            artifact: first_tag(&outer_xml, "artifactId").map(str::to_owned),
+            version: first_tag(&outer_xml, "version").map(str::to_owned),
+            versioning: Versioning {
+                latest: first_tag(versioning_xml, "latest").map(str::to_owned),
+                release: first_tag(versioning_xml, "release").map(str::to_owned),
+                versions: first_tag(versioning_xml, "versions")
+                    .map(|versions| all_tags(versions, "version").into_iter().map(str::to_owned).collect())
+                    .unwrap_or_else(Vec::new),
+                last_updated: first_tag(versioning_xml, "lastUpdated").map(str::to_owned),
+                snapshot,
+                snapshot_versions,
+            },
+        }
+    }
+
+    fn newest<'a, I: Iterator<Item = &'a String>>(versions: I) -> Option<String> {
+        versions
+            .max_by(|a, b| ComparableVersion::new(a).cmp(&ComparableVersion::new(b)))
+            .cloned()
+    }
+
+    ///Picks the version `request` refers to from the versions listed in this (artifact level) metadata
+    ///
+    ///Snapshot requests can't be answered here, they need the metadata for that version, see `snapshot_build`
+    pub fn select(&self, request: &VersionRequest) -> Option<String> {
+        let versioning = &self.versioning;
+        match *request {
+            VersionRequest::Exact(ref version) => versioning.versions.iter().find(|v| *v == version).cloned(),
+            VersionRequest::Snapshot(_) => None,
+            VersionRequest::Latest => versioning.latest.clone().or_else(|| Self::newest(versioning.versions.iter())),
+            VersionRequest::Release => versioning.release.clone().or_else(|| {
+                Self::newest(versioning.versions.iter().filter(|v| !version::is_snapshot(v)))
+            }),
+            VersionRequest::Range(ref range) => Self::newest(
+                versioning.versions.iter().filter(|v| !version::is_snapshot(v) && range.contains_str(v))
+            ),
+        }
+    }
+
+    ///Adds a newly published release to this (artifact level) metadata, updating latest and release to match
+    pub fn add_version(&mut self, version: &str, last_updated: String) {
+        let versioning = &mut self.versioning;
+        if !versioning.versions.iter().any(|v| v == version) {
+            versioning.versions.push(version.to_owned());
+        }
+        versioning.versions.sort_by(|a, b| ComparableVersion::new(a).cmp(&ComparableVersion::new(b)));
+        versioning.latest = versioning.versions.last().cloned();
+        versioning.release = Self::newest(versioning.versions.iter().filter(|v| !version::is_snapshot(v)));
+        versioning.last_updated = Some(last_updated);
+    }
+
+    ///Writes this out as a maven-metadata.xml
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<metadata>\n");
+        if let Some(ref group) = self.group {
+            write_tag(&mut xml, 1, "groupId", group);
+        }
+        if let Some(ref artifact) = self.artifact {
+            write_tag(&mut xml, 1, "artifactId", artifact);
+        }
+        if let Some(ref version) = self.version {
+            write_tag(&mut xml, 1, "version", version);
+        }
+        let versioning = &self.versioning;
+        xml.push_str("  <versioning>\n");
+        if let Some(ref latest) = versioning.latest {
+            write_tag(&mut xml, 2, "latest", latest);
+        }
+        if let Some(ref release) = versioning.release {
+            write_tag(&mut xml, 2, "release", release);
+        }
+        if let Some(ref snapshot) = versioning.snapshot {
+            xml.push_str("    <snapshot>\n");
+            write_tag(&mut xml, 3, "timestamp", &snapshot.timestamp);
+            write_tag(&mut xml, 3, "buildNumber", &snapshot.build_number.to_string());
+            xml.push_str("    </snapshot>\n");
+        }
+        if !versioning.versions.is_empty() {
+            xml.push_str("    <versions>\n");
+            for version in &versioning.versions {
+                write_tag(&mut xml, 3, "version", version);
+            }
+            xml.push_str("    </versions>\n");
+        }
+        if let Some(ref last_updated) = versioning.last_updated {
+            write_tag(&mut xml, 2, "lastUpdated", last_updated);
+        }
+        if !versioning.snapshot_versions.is_empty() {
+            xml.push_str("    <snapshotVersions>\n");
+            for snapshot_version in &versioning.snapshot_versions {
+                xml.push_str("      <snapshotVersion>\n");
+                if let Some(ref classifier) = snapshot_version.classifier {
+                    write_tag(&mut xml, 4, "classifier", classifier);
+                }
+                write_tag(&mut xml, 4, "extension", &snapshot_version.extension);
+                write_tag(&mut xml, 4, "value", &snapshot_version.value);
+                xml.push_str("      </snapshotVersion>\n");
+            }
+            xml.push_str("    </snapshotVersions>\n");
+        }
+        xml.push_str("  </versioning>\n</metadata>\n");
+        xml
+    }
+
+    ///The timestamped build of a snapshot, from the metadata for that snapshot version
+    pub fn snapshot_build(&self, classifier: Option<&str>, extension: &str) -> Option<String> {
+        let versioning = &self.versioning;
+        if !versioning.snapshot_versions.is_empty() {
+            //a file which isn't listed wasn't published in this build, and the build's other files would be the wrong ones
+            return versioning.snapshot_versions.iter()
+                .find(|snapshot| snapshot.classifier.as_ref().map(String::as_str) == classifier && snapshot.extension == extension)
+                .map(|snapshot| snapshot.value.clone());
+        }
+        //older metadata only lists the latest timestamp and build number
+        let snapshot = versioning.snapshot.as_ref()?;
+        let base = self.version.as_ref()?.trim_end_matches("-SNAPSHOT");
+        if snapshot.timestamp.is_empty() {
+            None
+        } else {
+            Some(format!("{}-{}-{}", base, snapshot.timestamp, snapshot.build_number))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ARTIFACT_METADATA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>mezz.jei</groupId>
+  <artifactId>jei_1.12.2</artifactId>
+  <versioning>
+    <release>4.15.0.268</release>
+    <versions>
+      <version>4.9.0.1</version>
+      <version>4.15.0.268</version>
+      <version>4.16.0-SNAPSHOT</version>
+      <version>4.10.0.200</version>
+    </versions>
+    <lastUpdated>20190612105713</lastUpdated>
+  </versioning>
+</metadata>"#;
+
+    const SNAPSHOT_METADATA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata modelVersion="1.1.0">
+  <groupId>mezz.jei</groupId>
+  <artifactId>jei_1.12.2</artifactId>
+  <version>4.16.0-SNAPSHOT</version>
+  <versioning>
+    <snapshot>
+      <timestamp>20190612.105713</timestamp>
+      <buildNumber>3</buildNumber>
+    </snapshot>
+    <lastUpdated>20190612105713</lastUpdated>
+    <snapshotVersions>
+      <snapshotVersion>
+        <classifier>api</classifier>
+        <extension>jar</extension>
+        <value>4.16.0-20190612.105700-2</value>
+      </snapshotVersion>
+      <snapshotVersion>
+        <extension>jar</extension>
+        <value>4.16.0-20190612.105713-3</value>
+      </snapshotVersion>
+    </snapshotVersions>
+  </versioning>
+</metadata>"#;
+
+    #[test]
+    fn parses_artifact_metadata() {
+        let metadata = Metadata::parse(ARTIFACT_METADATA);
+        assert_eq!(metadata.group, Some("mezz.jei".into()));
+        assert_eq!(metadata.artifact, Some("jei_1.12.2".into()));
+        assert_eq!(metadata.version, None);
+        assert_eq!(metadata.versioning.versions.len(), 4);
+        assert_eq!(metadata.versioning.release, Some("4.15.0.268".into()));
+    }
+
+    #[test]
+    fn selects_versions() {
+        let metadata = Metadata::parse(ARTIFACT_METADATA);
+        assert_eq!(metadata.select(&VersionRequest::Latest), Some("4.16.0-SNAPSHOT".into()));
+        assert_eq!(metadata.select(&VersionRequest::Release), Some("4.15.0.268".into()));
+        assert_eq!(metadata.select(&VersionRequest::parse("[4.0,4.12)").unwrap()), Some("4.10.0.200".into()));
+        assert_eq!(metadata.select(&VersionRequest::parse("[5.0,)").unwrap()), None);
+    }
+
+    #[test]
+    fn resolves_snapshot_builds() {
+        let metadata = Metadata::parse(SNAPSHOT_METADATA);
+        assert_eq!(metadata.version, Some("4.16.0-SNAPSHOT".into()));
+        assert_eq!(metadata.snapshot_build(None, "jar"), Some("4.16.0-20190612.105713-3".into()));
+        assert_eq!(metadata.snapshot_build(Some("api"), "jar"), Some("4.16.0-20190612.105700-2".into()));
+        assert_eq!(metadata.snapshot_build(Some("sources"), "jar"), None);
+
+        let mut older = metadata.clone();
+        older.versioning.snapshot_versions.clear();
+        assert_eq!(older.snapshot_build(Some("sources"), "jar"), Some("4.16.0-20190612.105713-3".into()));
+    }
+
+    #[test]
+    fn round_trips_through_xml() {
+        for xml in [ARTIFACT_METADATA, SNAPSHOT_METADATA].iter() {
+            let metadata = Metadata::parse(xml);
+            assert_eq!(Metadata::parse(&metadata.to_xml()), metadata);
+        }
+    }
+
+    #[test]
+    fn adds_versions() {
+        let mut metadata = Metadata::parse(ARTIFACT_METADATA);
+        metadata.add_version("4.15.1.1", "20190701000000".into());
+        assert_eq!(metadata.versioning.release, Some("4.15.1.1".into()));
+        assert_eq!(metadata.versioning.latest, Some("4.16.0-SNAPSHOT".into()));
+        assert_eq!(metadata.versioning.versions.len(), 5);
+        metadata.add_version("4.15.1.1", "20190702000000".into());
+        assert_eq!(metadata.versioning.versions.len(), 5);
+        assert_eq!(metadata.versioning.last_updated, Some("20190702000000".into()));
+    }
+}
@@ -12,10 +12,17 @@ use crate::{
     error::prelude::*,
 };
 use tokio::io::AsyncReadExt;
-mod hash_writer;
-use hash_writer::HashWriter;
+pub mod hash_writer;
+use hash_writer::{HashAlgorithm, HashWriter};
+mod repository;
+pub use repository::{Repository, Mirror, RepositoryConfig};
+pub mod version;
+use version::VersionRequest;
+pub mod metadata;
+use metadata::Metadata;
+pub mod publish;
 
-mod error{
+pub mod error{
     use snafu::Snafu;
     #[derive(Debug,Snafu)]
     #[snafu(visibility(pub))]
@@ -28,17 +35,105 @@ mod error{
         BadUrl{
             source: url::ParseError,
         },
+        #[snafu(display("{}", source))]
+        BadVersion{
+            source: super::version::Error,
+        },
+        #[snafu(display("Error {} while fetching {}", source, uri))]
+        Download{
+            uri: http::Uri,
+            source: crate::download::Error,
+        },
+        #[snafu(display("None of the repositories have maven metadata for {}", artifact))]
+        NoMetadata{
+            artifact: String,
+        },
+        #[snafu(display("No version of {} matches the requested version", artifact))]
+        NoMatchingVersion{
+            artifact: String,
+        },
+        #[snafu(display("Io error {} while hashing {}", source, path))]
+        HashingFile{
+            path: String,
+            source: std::io::Error,
+        },
+        #[snafu(display("{} failed verification: expected {} {}, but got {}", artifact, algorithm, expected, actual))]
+        HashMismatch{
+            artifact: String,
+            algorithm: super::hash_writer::HashAlgorithm,
+            expected: String,
+            actual: String,
+        },
     }
 }
+pub use error::Error;
+
+lazy_static! {
+    static ref TIMESTAMPED_SNAPSHOT: regex::Regex = regex::Regex::new(r"^(.*)-(\d{8}\.\d{6})-(\d+)$").expect("const regex should always be valid");
+}
 
 #[derive(Debug)]
 pub enum VerifyResult {
-    Good,
-    Bad,
+    Good(HashAlgorithm),
+    Bad{
+        algorithm: HashAlgorithm,
+        expected: String,
+        actual: String,
+    },
+    ///The repository doesn't publish any checksums we understand for this artifact
+    NoChecksums,
     NotInCache,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+fn checksum_uri(artifact_uri: &Uri, algorithm: HashAlgorithm) -> Result<Uri, Error> {
+    let mut url = crate::util::uri_to_url(artifact_uri).context(error::BadUrl)?;
+    let path = format!("{}.{}", url.path(), algorithm.extension());
+    url.set_path(path.as_ref());
+    crate::util::url_to_uri(&url).context(error::BadUri)
+}
+
+async fn hash_file(path: PathBuf, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hash = HashWriter::with_algorithm(algorithm);
+    file.copy(&mut hash).await?;
+    Ok(hash.digest())
+}
+
+///Checks the file at `path` against the strongest checksum published alongside `artifact_uri`
+async fn verify_file(path: PathBuf, artifact_uri: Uri, manager: download::Manager) -> Result<VerifyResult, Error> {
+    for &algorithm in HashAlgorithm::ALL.iter() {
+        let uri = checksum_uri(&artifact_uri, algorithm)?;
+        let res = match manager.get(uri.clone()) {
+            Ok(follower) => follower.await,
+            Err(e) => Err(e),
+        };
+        let res = match res {
+            Ok((res, _url)) => res,
+            //no checksum of this type
+            Err(download::Error::HttpClient{..}) => continue,
+            Err(e) => return Err(error::Download{uri}.into_error(e)),
+        };
+        let body = res.into_body()
+            .map_ok(hyper::Chunk::into_bytes)
+            .try_concat()
+            .await
+            .context(download::error::Hyper)
+            .context(error::Download{uri})?;
+        let expected = match algorithm.parse_checksum_file(&String::from_utf8_lossy(&body)) {
+            Some(expected) => expected,
+            None => continue,
+        };
+        let actual = hash_file(path.clone(), algorithm).await.context(error::HashingFile{path: path.display().to_string()})?;
+        return Ok(if actual == expected {
+            VerifyResult::Good(algorithm)
+        } else {
+            VerifyResult::Bad{ algorithm, expected, actual }
+        });
+    }
+    Ok(VerifyResult::NoChecksums)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, schemars::JsonSchema)]
 pub struct Artifact {
     pub group: String,
     pub artifact: String,
@@ -50,13 +145,14 @@ pub struct Artifact {
 #[derive(Debug, Clone)]
 pub struct ResolvedArtifact {
     pub artifact: Artifact,
-    pub repo: Uri,
+    ///Repositories to try fetching from, in order
+    pub repos: Vec<Uri>,
 }
 
 pub struct Cache;
 
 impl Cacheable for ResolvedArtifact {
-    type Cache = crate::cache::FileCache;
+    type Cache = Cache;
     fn cached_path(&self) -> PathBuf {
         let mut p = PathBuf::new();
         p.push(app_dirs::app_dir(app_dirs::AppDataType::UserCache, crate::APP_INFO, "maven_cache").expect("Cache directory must be accesible"));
@@ -64,7 +160,8 @@ impl Cacheable for ResolvedArtifact {
         p
     }
     fn uri(&self) -> crate::cache::Result<Uri> {
-        crate::cache::ResultExt::erased(self.artifact.get_uri_on(&self.repo))
+        let repo = self.repos.first().context(cache::error::NoRepository{item: self.artifact.to_string()})?;
+        crate::cache::ResultExt::erased(self.artifact.get_uri_on(repo))
     }
 }
 
@@ -76,15 +173,50 @@ impl cache::Cache<ResolvedArtifact> for Cache {
     ) -> crate::cache::BoxFuture<PathBuf> {
         let cached_path = artifact.cached_path();
         let log = log.new(
-            o!("artifact"=>artifact.artifact.to_string(),"repo"=>artifact.repo.to_string(),"cached_path"=>cached_path.as_path().to_string_lossy().into_owned()),
+            o!("artifact"=>artifact.artifact.to_string(),"cached_path"=>cached_path.as_path().to_string_lossy().into_owned()),
         );
         Box::pin(async move{
             info!(log, "caching maven artifact");
             if !Self::is_cached(&artifact) {
                 info!(log, "artifact is not cached, downloading now");
-                let uri = artifact.uri()?;
-                manager
-                    .download(uri.clone(), cached_path.clone(), false, &log).await.context(crate::cache::error::Downloading{uri})?;
+                let mut last_err = None;
+                for repo in &artifact.repos {
+                    let uri = crate::cache::ResultExt::erased(artifact.artifact.get_uri_on(repo))?;
+                    match manager.download(uri.clone(), cached_path.clone(), false, &log).await {
+                        Ok(()) => {
+                            let item = artifact.artifact.to_string();
+                            let verified = verify_file(cached_path.clone(), uri, manager.clone()).await
+                                .context(crate::cache::error::Verifying{item: item.clone()})?;
+                            match verified {
+                                VerifyResult::Bad{algorithm, expected, actual} => {
+                                    if let Err(e) = tokio::fs::remove_file(&cached_path).await {
+                                        warn!(log, "couldn't remove corrupt artifact: {}", e);
+                                    }
+                                    return error::HashMismatch{artifact: item.clone(), algorithm, expected, actual}.fail()
+                                        .context(crate::cache::error::Verifying{item});
+                                }
+                                VerifyResult::NoChecksums => {
+                                    warn!(log, "repo has no checksums for artifact, it can't be verified"; "repo"=>repo.to_string());
+                                }
+                                VerifyResult::Good(algorithm) => {
+                                    debug!(log, "verified artifact"; "algorithm"=>algorithm.extension());
+                                }
+                                VerifyResult::NotInCache => unreachable!("artifact was just downloaded"),
+                            }
+                            return Ok(cached_path);
+                        }
+                        //the artifact isn't in this repo, so try the next one
+                        Err(e @ download::Error::HttpClient{..}) => {
+                            warn!(log, "artifact not found in repo, trying next"; "repo"=>repo.to_string());
+                            last_err = Some(crate::cache::error::Downloading{uri}.into_error(e));
+                        }
+                        Err(e) => return Err(crate::cache::error::Downloading{uri}.into_error(e)),
+                    }
+                }
+                return match last_err {
+                    Some(e) => Err(e),
+                    None => crate::cache::error::NoRepository{item: artifact.artifact.to_string()}.fail(),
+                };
             }
             Ok(cached_path)
         })
@@ -92,30 +224,23 @@ impl cache::Cache<ResolvedArtifact> for Cache {
 }
 
 impl Cache {
+    ///Checks a cached artifact against the checksums published by the first of its repositories which has any
     pub async fn verify_cached(
         resolved: ResolvedArtifact,
         manager: download::Manager,
-    ) -> download::Result<VerifyResult> {
-        if Self::is_cached(&resolved) {
-            let cached_path = resolved.cached_path();
-            let sha_url_res = resolved.sha_uri();
-            let mut cached_file = tokio::fs::File::open(cached_path).await.context(download::error::Io)?;
-
-            let mut sha = HashWriter::new();
-            cached_file.copy(&mut sha).await.context(download::error::Io)?;
-            let cached_sha = sha.digest();
-
-            let sha_uri = sha_url_res?;
-            let (res,_) = manager.get(sha_uri)?.await?;
-            let hash_str = res.into_body().map_ok(hyper::Chunk::into_bytes).try_concat().await.context(download::error::Hyper)?;
-            if hash_str == format!("{}", cached_sha) {
-                Ok(VerifyResult::Good)
-            } else {
-                Ok(VerifyResult::Bad)
+    ) -> Result<VerifyResult, Error> {
+        if !Self::is_cached(&resolved) {
+            return Ok(VerifyResult::NotInCache);
+        }
+        let cached_path = resolved.cached_path();
+        for repo in &resolved.repos {
+            let uri = resolved.artifact.get_uri_on(repo)?;
+            match verify_file(cached_path.clone(), uri, manager.clone()).await? {
+                VerifyResult::NoChecksums => {}
+                result => return Ok(result),
             }
-        } else {
-            Ok(VerifyResult::NotInCache)
         }
+        Ok(VerifyResult::NoChecksums)
     }
 }
 
@@ -124,11 +249,99 @@ impl Artifact {
         let mut p = PathBuf::new();
         p.push(&self.group_path());
         p.push(&self.artifact);
-        p.push(&self.version);
+        p.push(&self.base_version());
         p.push(&self.artifact_filename());
         p
     }
 
+    ///The version of the folder this artifact lives in, timestamped snapshot builds live in their `-SNAPSHOT` folder
+    pub fn base_version(&self) -> String {
+        match TIMESTAMPED_SNAPSHOT.captures(&self.version) {
+            Some(captures) => format!("{}-SNAPSHOT", &captures[1]),
+            None => self.version.clone(),
+        }
+    }
+
+    fn metadata_path(&self, version: Option<&str>) -> String {
+        let mut parts: Vec<&str> = self.group.split('.').collect();
+        parts.push(&self.artifact);
+        if let Some(version) = version {
+            parts.push(version);
+        }
+        parts.push("maven-metadata.xml");
+        parts.join("/")
+    }
+
+    ///Where the maven-metadata.xml for this artifact (or for one version of it) is on the repository at `base`
+    pub fn get_metadata_uri_on(&self, base: &Uri, version: Option<&str>) -> Result<Uri, Error> {
+        let base = crate::util::uri_to_url(base).context(error::BadUrl)?;
+        let url = base.join(&self.metadata_path(version)).context(error::BadUrl)?;
+        crate::util::url_to_uri(&url).context(error::BadUri)
+    }
+
+    ///Fetches the maven-metadata.xml for this artifact (or for one version of it) from the first of `repos` which has one
+    pub async fn fetch_metadata(self, version: Option<String>, repos: Vec<Uri>, manager: download::Manager) -> Result<Metadata, Error> {
+        for repo in repos {
+            let uri = self.get_metadata_uri_on(&repo, version.as_ref().map(String::as_str))?;
+            let res = match manager.get(uri.clone()) {
+                Ok(follower) => follower.await,
+                Err(e) => Err(e),
+            };
+            match res {
+                Ok((res, _url)) => {
+                    let body = res.into_body()
+                        .map_ok(hyper::Chunk::into_bytes)
+                        .try_concat()
+                        .await
+                        .context(download::error::Hyper)
+                        .context(error::Download{uri})?;
+                    return Ok(Metadata::parse(&String::from_utf8_lossy(&body)));
+                }
+                //not in this repo
+                Err(download::Error::HttpClient{..}) => {}
+                Err(e) => return Err(error::Download{uri}.into_error(e)),
+            }
+        }
+        error::NoMetadata{artifact: self.to_string()}.fail()
+    }
+
+    async fn resolve_snapshot(self, repos: Vec<Uri>, manager: download::Manager) -> Result<String, Error> {
+        let extension = self.extension.clone().unwrap_or_else(|| "jar".into());
+        let metadata = self.clone().fetch_metadata(Some(self.version.clone()), repos, manager).await?;
+        metadata.snapshot_build(self.classifier.as_ref().map(String::as_str), &extension)
+            .context(error::NoMatchingVersion{artifact: self.to_string()})
+    }
+
+    ///Turns this artifact's version (which may be a range, `LATEST`, `RELEASE` or a `-SNAPSHOT`) into an exact version,
+    ///using the repositories' metadata
+    pub async fn resolve_version(self, repos: Vec<Uri>, manager: download::Manager) -> Result<String, Error> {
+        let request = VersionRequest::parse(&self.version).context(error::BadVersion)?;
+        let selected = match request {
+            VersionRequest::Exact(version) => return Ok(version),
+            VersionRequest::Snapshot(version) => version,
+            request => {
+                let metadata = self.clone().fetch_metadata(None, repos.clone(), manager.clone()).await?;
+                metadata.select(&request).context(error::NoMatchingVersion{artifact: self.to_string()})?
+            }
+        };
+        if version::is_snapshot(&selected) {
+            Self{ version: selected, ..self }.resolve_snapshot(repos, manager).await
+        } else {
+            Ok(selected)
+        }
+    }
+
+    ///The newest version available for this artifact.
+    ///
+    ///Dynamic versions are resolved as normal, exact versions are compared against the newest release.
+    pub async fn newest_version(self, repos: Vec<Uri>, manager: download::Manager) -> Result<String, Error> {
+        if VersionRequest::parse(&self.version).context(error::BadVersion)?.is_dynamic() {
+            self.resolve_version(repos, manager).await
+        } else {
+            Self{ version: "RELEASE".into(), ..self }.resolve_version(repos, manager).await
+        }
+    }
+
     pub fn get_uri_on(&self, base: &Uri) -> Result<Uri,error::Error> {
         let base = crate::util::uri_to_url(base).context(error::BadUrl)?;
         let path = self.to_path();
@@ -159,12 +372,21 @@ impl Artifact {
     }
 
     pub fn resolve(&self, repo_uri: Uri) -> ResolvedArtifact {
+        self.resolve_on(vec![repo_uri])
+    }
+
+    pub fn resolve_on(&self, repos: Vec<Uri>) -> ResolvedArtifact {
         ResolvedArtifact {
             artifact: self.clone(),
-            repo: repo_uri,
+            repos,
         }
     }
 
+    ///Resolves this artifact on every repository in `config` it could be found in, trying `preferred` before any fallbacks
+    pub fn resolve_with(&self, config: &RepositoryConfig, preferred: Option<&Uri>) -> ResolvedArtifact {
+        self.resolve_on(config.repos_for(&self.group, preferred))
+    }
+
     pub fn download_from(
         &self,
         location: &Path,
@@ -180,13 +402,6 @@ impl ResolvedArtifact {
     pub fn to_path(&self) -> PathBuf {
         self.artifact.to_path()
     }
-    pub fn sha_uri(&self) -> crate::download::Result<Uri> {
-        let mut url = crate::util::uri_to_url(&self.uri().context(download::error::Cached)?).context(download::error::BadUrl)?;
-        let mut path = url.path().to_owned();
-        path.push_str(".sha1");
-        url.set_path(path.as_ref());
-        crate::util::url_to_uri(&url).context(download::error::BadUri)
-    }
     pub fn install_at_no_classifier(
         self,
         location: PathBuf,
@@ -199,7 +414,7 @@ impl ResolvedArtifact {
                     classifier: None,
                     ..self.artifact.clone()
                 },
-                repo: self.repo.clone(),
+                repos: self.repos.clone(),
             }.cached_path();
 
             let filename = cached_path_no_classifier.file_name().expect("Maven artifact should have a filename");
@@ -320,4 +535,11 @@ mod test {
             })
         )
     }
+    #[test]
+    fn timestamped_snapshots_live_in_snapshot_folder() {
+        let artifact: Artifact = "mezz.jei:jei_1.12.2:4.16.0-20190612.105713-3".parse().unwrap();
+        assert_eq!(artifact.base_version(), "4.16.0-SNAPSHOT");
+        let artifact: Artifact = "mezz.jei:jei_1.12.2:4.15.0.268".parse().unwrap();
+        assert_eq!(artifact.base_version(), "4.15.0.268");
+    }
 }
@@ -0,0 +1,282 @@
+use futures::prelude::*;
+use http::{Request, Uri};
+use snafu::Snafu;
+use std::{
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+};
+use crate::{
+    download::HttpSimple,
+    error::prelude::*,
+};
+use super::{
+    Artifact,
+    checksum_uri,
+    hash_writer::{HashAlgorithm, HashWriter},
+    metadata::{escape_xml, Metadata},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Invalid repository url {}: {}", url, source))]
+    BadRepoUrl{
+        url: String,
+        source: http::uri::InvalidUri,
+    },
+    #[snafu(display("Couldn't build upload url for {}: {}", artifact, source))]
+    BadArtifactUri{
+        artifact: String,
+        #[snafu(source(from(super::Error, Box::new)))]
+        source: Box<super::Error>,
+    },
+    #[snafu(display("Http error {} while uploading {}", source, uri))]
+    Http{
+        uri: Uri,
+        source: hyper::Error,
+    },
+    #[snafu(display("The repository rejected {} with status {}", uri, status))]
+    Rejected{
+        uri: Uri,
+        status: http::StatusCode,
+    },
+    #[snafu(display("Io error {} while writing {}", source, path))]
+    Io{
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+///A file in a repository which belongs to an artifact
+#[derive(Debug, Clone)]
+enum RepoFile {
+    ///One of the artifact's own files (e.g. the main file, a classified extra, or the pom)
+    Artifact(Artifact),
+    ///The maven-metadata.xml listing the artifact's versions
+    Metadata(Artifact),
+}
+
+impl RepoFile {
+    ///The file's path within the repository, or that of its checksum
+    fn path(&self, checksum: Option<HashAlgorithm>) -> String {
+        let path = match *self {
+            RepoFile::Artifact(ref artifact) => {
+                let parts: Vec<_> = artifact.to_path().iter().map(|part| part.to_string_lossy().into_owned()).collect();
+                parts.join("/")
+            }
+            RepoFile::Metadata(ref artifact) => artifact.metadata_path(None),
+        };
+        match checksum {
+            Some(algorithm) => format!("{}.{}", path, algorithm.extension()),
+            None => path,
+        }
+    }
+
+    ///Where the file, or its checksum, is on the repository at `base`
+    fn uri_on(&self, base: &Uri, checksum: Option<HashAlgorithm>) -> Result<Uri> {
+        let (uri, artifact) = match *self {
+            RepoFile::Artifact(ref artifact) => (artifact.get_uri_on(base), artifact),
+            RepoFile::Metadata(ref artifact) => (artifact.get_metadata_uri_on(base, None), artifact),
+        };
+        let uri = match checksum {
+            Some(algorithm) => uri.and_then(|uri| checksum_uri(&uri, algorithm)),
+            None => uri,
+        };
+        uri.context(BadArtifactUri{artifact: artifact.to_string()})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    fn header_value(&self) -> String {
+        format!("Basic {}", base64::encode(&format!("{}:{}", self.username, self.password)))
+    }
+}
+
+enum Target {
+    Http{
+        base: Uri,
+        credentials: Option<Credentials>,
+        client: HttpSimple,
+    },
+    ///A repository on the local filesystem, or a network share
+    Directory(PathBuf),
+}
+
+///Uploads artifacts to a maven repository, along with their checksums and metadata
+pub struct Publisher {
+    target: Target,
+}
+
+impl Publisher {
+    ///`repo` is either a http(s) url, or a `file://` url or path for a directory backed repository
+    pub fn new(repo: &str, credentials: Option<Credentials>) -> Result<Self> {
+        let target = if repo.starts_with("http://") || repo.starts_with("https://") {
+            Target::Http{
+                base: Uri::from_str(repo).context(BadRepoUrl{url: repo})?,
+                credentials,
+                client: HttpSimple::new(),
+            }
+        } else {
+            Target::Directory(PathBuf::from(repo.trim_start_matches("file://")))
+        };
+        Ok(Self{ target })
+    }
+
+    async fn put(&self, file: &RepoFile, checksum: Option<HashAlgorithm>, data: Vec<u8>) -> Result<()> {
+        match self.target {
+            Target::Http{ref base, ref credentials, ref client} => {
+                let uri = file.uri_on(base, checksum)?;
+                let mut request = Request::builder();
+                request.method(http::Method::PUT).uri(uri.clone());
+                if let Some(ref credentials) = *credentials {
+                    request.header(http::header::AUTHORIZATION, credentials.header_value().as_str());
+                }
+                let request = request.body(hyper::Body::from(data)).expect("error building request");
+                let res = client.request(request).await.context(Http{uri: uri.clone()})?;
+                if res.status().is_success() {
+                    Ok(())
+                } else {
+                    Rejected{uri, status: res.status()}.fail()
+                }
+            }
+            Target::Directory(ref root) => {
+                let mut target = root.clone();
+                target.extend(file.path(checksum).split('/'));
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent).context(Io{path: parent.display().to_string()})?;
+                }
+                std::fs::write(&target, data).context(Io{path: target.display().to_string()})
+            }
+        }
+    }
+
+    ///Fetches an existing file from the repository, or None if it doesn't exist yet
+    async fn get(&self, file: &RepoFile) -> Result<Option<Vec<u8>>> {
+        match self.target {
+            Target::Http{ref base, ref credentials, ref client} => {
+                let uri = file.uri_on(base, None)?;
+                let mut request = Request::builder();
+                request.method(http::Method::GET).uri(uri.clone());
+                if let Some(ref credentials) = *credentials {
+                    request.header(http::header::AUTHORIZATION, credentials.header_value().as_str());
+                }
+                let request = request.body(hyper::Body::empty()).expect("error building request");
+                let res = client.request(request).await.context(Http{uri: uri.clone()})?;
+                match res.status() {
+                    http::StatusCode::NOT_FOUND => Ok(None),
+                    status if status.is_success() => {
+                        let body = res.into_body().map_ok(hyper::Chunk::into_bytes).try_concat().await.context(Http{uri})?;
+                        Ok(Some(body.to_vec()))
+                    }
+                    status => Rejected{uri, status}.fail(),
+                }
+            }
+            Target::Directory(ref root) => {
+                let mut target = root.clone();
+                target.extend(file.path(None).split('/'));
+                match std::fs::read(&target) {
+                    Ok(data) => Ok(Some(data)),
+                    Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(Io{path: target.display().to_string()}.into_error(e)),
+                }
+            }
+        }
+    }
+
+    ///Uploads a checksum file for every supported algorithm, followed by the file itself
+    async fn put_with_checksums(&self, file: RepoFile, data: Vec<u8>) -> Result<()> {
+        for &algorithm in HashAlgorithm::ALL.iter() {
+            let mut hash = HashWriter::with_algorithm(algorithm);
+            hash.write_all(&data).expect("HashWriter can't fail");
+            self.put(&file, Some(algorithm), hash.digest().into_bytes()).await?;
+        }
+        self.put(&file, None, data).await
+    }
+
+    ///Uploads one file of an artifact (e.g. the main file, or a classified extra)
+    pub async fn publish_file(&self, artifact: Artifact, data: Vec<u8>) -> Result<()> {
+        self.put_with_checksums(RepoFile::Artifact(artifact), data).await
+    }
+
+    ///Uploads a generated pom for `artifact`
+    pub async fn publish_pom(&self, artifact: Artifact, name: String, packaging: String) -> Result<()> {
+        let pom_artifact = Artifact{
+            classifier: None,
+            extension: Some("pom".into()),
+            ..artifact
+        };
+        let pom = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <project xmlns=\"http://maven.apache.org/POM/4.0.0\" \
+             xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+             xsi:schemaLocation=\"http://maven.apache.org/POM/4.0.0 http://maven.apache.org/xsd/maven-4.0.0.xsd\">\n\
+             \x20 <modelVersion>4.0.0</modelVersion>\n\
+             \x20 <groupId>{}</groupId>\n\
+             \x20 <artifactId>{}</artifactId>\n\
+             \x20 <version>{}</version>\n\
+             \x20 <packaging>{}</packaging>\n\
+             \x20 <name>{}</name>\n\
+             </project>\n",
+            escape_xml(&pom_artifact.group),
+            escape_xml(&pom_artifact.artifact),
+            escape_xml(&pom_artifact.version),
+            escape_xml(&packaging),
+            escape_xml(&name),
+        );
+        self.put_with_checksums(RepoFile::Artifact(pom_artifact), pom.into_bytes()).await
+    }
+
+    ///Adds `artifact`'s version to the artifact's maven-metadata.xml, creating it if needed
+    pub async fn update_metadata(&self, artifact: Artifact) -> Result<()> {
+        let file = RepoFile::Metadata(artifact.clone());
+        let mut metadata = match self.get(&file).await? {
+            Some(existing) => Metadata::parse(&String::from_utf8_lossy(&existing)),
+            None => Metadata{
+                group: Some(artifact.group.clone()),
+                artifact: Some(artifact.artifact.clone()),
+                ..Metadata::default()
+            },
+        };
+        metadata.add_version(&artifact.version, chrono::Utc::now().format("%Y%m%d%H%M%S").to_string());
+        self.put_with_checksums(file, metadata.to_xml().into_bytes()).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn publishes_to_directory() {
+        let mut root = std::env::temp_dir();
+        root.push(format!("modpack_tool_publish_test_{}", std::process::id()));
+        let publisher = Publisher::new(root.to_str().unwrap(), None).unwrap();
+        let artifact: Artifact = "com.example.packs:test_pack:1.0.0@json".parse().unwrap();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            publisher.publish_file(artifact.clone(), b"{}".to_vec()).await.unwrap();
+            publisher.publish_pom(artifact.clone(), "Test Pack".into(), "json".into()).await.unwrap();
+            publisher.update_metadata(artifact.clone()).await.unwrap();
+            publisher.update_metadata(Artifact{ version: "1.1.0".into(), ..artifact }).await.unwrap();
+        });
+
+        let base = root.join("com/example/packs/test_pack");
+        assert_eq!(std::fs::read(base.join("1.0.0/test_pack-1.0.0.json")).unwrap(), b"{}");
+        assert!(base.join("1.0.0/test_pack-1.0.0.json.sha1").exists());
+        assert!(base.join("1.0.0/test_pack-1.0.0.pom").exists());
+        let metadata = Metadata::parse(&std::fs::read_to_string(base.join("maven-metadata.xml")).unwrap());
+        assert_eq!(metadata.versioning.versions, vec!["1.0.0".to_owned(), "1.1.0".to_owned()]);
+        assert_eq!(metadata.versioning.release, Some("1.1.0".into()));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use http::Uri;
+use schemars::JsonSchema;
+use std::{
+    path::PathBuf,
+    str::FromStr,
+};
+
+///Where artifacts are fetched from when a repository doesn't route them elsewhere
+const MAVEN_CENTRAL: &str = "https://repo1.maven.org/maven2/";
+
+const GLOBAL_CONFIG_FILE: &str = "repositories.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Repository {
+    #[serde(with = "crate::util::serde_uri")]
+    #[schemars(with = "String")]
+    pub url: Uri,
+    ///Groups (and their subgroups) which should always be fetched from this repository.
+    ///If empty the repository is a fallback for every group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+impl Repository {
+    fn routes(&self, group: &str) -> bool {
+        self.groups.iter().any(|routed| {
+            group == routed || (group.starts_with(routed.as_str()) && group[routed.len()..].starts_with('.'))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Mirror {
+    ///The repository being mirrored
+    #[serde(with = "crate::util::serde_uri")]
+    #[schemars(with = "String")]
+    pub of: Uri,
+    ///The repository to use in its place
+    #[serde(with = "crate::util::serde_uri")]
+    #[schemars(with = "String")]
+    pub url: Uri,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, JsonSchema)]
+pub struct RepositoryConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repositories: Vec<Repository>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<Mirror>,
+}
+
+lazy_static! {
+    static ref GLOBAL: RepositoryConfig = RepositoryConfig::load_global();
+}
+
+impl RepositoryConfig {
+    ///The repositories we always know about.
+    ///
+    ///files.minecraftforge.net is missing the typesafe libraries forge depends on, so those come from maven central.
+    pub fn builtin() -> Self {
+        Self {
+            repositories: vec![
+                Repository {
+                    url: Uri::from_str(MAVEN_CENTRAL).expect("const Uri should always be valid"),
+                    groups: vec!["com.typesafe".into()],
+                },
+            ],
+            mirrors: vec![],
+        }
+    }
+
+    ///The user's global repository config, followed by the builtin repositories
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    pub fn global_config_path() -> PathBuf {
+        let mut p = app_dirs::get_app_root(app_dirs::AppDataType::UserConfig, crate::APP_INFO).expect("Config directory must be accesible");
+        p.push(GLOBAL_CONFIG_FILE);
+        p
+    }
+
+    fn load_global() -> Self {
+        let path = Self::global_config_path();
+        let user: Self = match std::fs::File::open(&path) {
+            Ok(file) => match serde_json::from_reader(file) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Ignoring invalid repository config at {}: {}", path.display(), e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        };
+        user.merged_with(&Self::builtin())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.repositories.is_empty() && self.mirrors.is_empty()
+    }
+
+    ///Combines two configs, with repositories and mirrors from `self` taking priority over those in `fallback`
+    pub fn merged_with(&self, fallback: &Self) -> Self {
+        let mut merged = self.clone();
+        for repo in &fallback.repositories {
+            if !merged.repositories.contains(repo) {
+                merged.repositories.push(repo.clone());
+            }
+        }
+        for mirror in &fallback.mirrors {
+            if !merged.mirrors.iter().any(|existing| existing.of == mirror.of) {
+                merged.mirrors.push(mirror.clone());
+            }
+        }
+        merged
+    }
+
+    fn mirrored(&self, url: &Uri) -> Uri {
+        self.mirrors.iter()
+            .find(|mirror| &mirror.of == url)
+            .map(|mirror| mirror.url.clone())
+            .unwrap_or_else(|| url.clone())
+    }
+
+    ///The repository `group` is routed to, if any
+    pub fn routed_repo(&self, group: &str) -> Option<Uri> {
+        self.repositories.iter()
+            .find(|repo| repo.routes(group))
+            .map(|repo| self.mirrored(&repo.url))
+    }
+
+    ///Every repository an artifact in `group` should be looked for in, in the order they should be tried.
+    ///
+    ///Routed repositories come first, then `preferred`, then all the fallback repositories.
+    pub fn repos_for(&self, group: &str, preferred: Option<&Uri>) -> Vec<Uri> {
+        let routed = self.repositories.iter()
+            .filter(|repo| repo.routes(group))
+            .map(|repo| &repo.url);
+        let fallback = self.repositories.iter()
+            .filter(|repo| repo.groups.is_empty())
+            .map(|repo| &repo.url);
+
+        let mut repos: Vec<Uri> = vec![];
+        for url in routed.chain(preferred).chain(fallback) {
+            let url = self.mirrored(url);
+            if !repos.contains(&url) {
+                repos.push(url);
+            }
+        }
+        repos
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn uri(s: &str) -> Uri {
+        Uri::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn routes_subgroups() {
+        let config = RepositoryConfig::builtin();
+        assert_eq!(config.routed_repo("com.typesafe"), Some(uri(MAVEN_CENTRAL)));
+        assert_eq!(config.routed_repo("com.typesafe.akka"), Some(uri(MAVEN_CENTRAL)));
+        assert_eq!(config.routed_repo("com.typesafeish"), None);
+        assert_eq!(config.routed_repo("net.minecraftforge"), None);
+    }
+
+    #[test]
+    fn orders_routed_preferred_then_fallback() {
+        let config = RepositoryConfig {
+            repositories: vec![
+                Repository{ url: uri("https://fallback.example/"), groups: vec![] },
+                Repository{ url: uri("https://routed.example/"), groups: vec!["org.example".into()] },
+            ],
+            mirrors: vec![],
+        };
+        assert_eq!(
+            config.repos_for("org.example.thing", Some(&uri("https://preferred.example/"))),
+            vec![uri("https://routed.example/"), uri("https://preferred.example/"), uri("https://fallback.example/")]
+        );
+        assert_eq!(
+            config.repos_for("net.other", Some(&uri("https://preferred.example/"))),
+            vec![uri("https://preferred.example/"), uri("https://fallback.example/")]
+        );
+    }
+
+    #[test]
+    fn applies_mirrors() {
+        let config = RepositoryConfig {
+            repositories: vec![],
+            mirrors: vec![Mirror{ of: uri("https://preferred.example/"), url: uri("https://mirror.example/") }],
+        };
+        assert_eq!(
+            config.repos_for("net.other", Some(&uri("https://preferred.example/"))),
+            vec![uri("https://mirror.example/")]
+        );
+    }
+}
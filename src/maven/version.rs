@@ -0,0 +1,342 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+};
+use snafu::Snafu;
+
+#[derive(Debug, Snafu, PartialEq, Eq)]
+pub enum Error {
+    #[snafu(display("Invalid maven version range: `{}`", range))]
+    BadVersionRange{
+        range: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Int(u64),
+    Qualifier(String),
+}
+
+///Where a qualifier sorts, relative to the others.
+///Well known qualifiers come first, in order, then anything else, alphabetically.
+fn qualifier_rank(qualifier: &str) -> (u8, &str) {
+    match qualifier {
+        "alpha" => (0, ""),
+        "beta" => (1, ""),
+        "milestone" => (2, ""),
+        "rc" => (3, ""),
+        "snapshot" => (4, ""),
+        "" => (5, ""),
+        "sp" => (6, ""),
+        other => (7, other),
+    }
+}
+
+fn normalize_qualifier(qualifier: &str) -> String {
+    let qualifier = qualifier.to_lowercase();
+    match qualifier.as_str() {
+        "a" => "alpha".into(),
+        "b" => "beta".into(),
+        "m" => "milestone".into(),
+        "cr" => "rc".into(),
+        "ga" | "final" | "release" => "".into(),
+        _ => qualifier,
+    }
+}
+
+///A maven version, ordered the way maven orders them (i.e. `1.0-alpha < 1.0-SNAPSHOT < 1.0 < 1.0-sp < 1.0.1`)
+#[derive(Debug, Clone)]
+pub struct ComparableVersion {
+    original: String,
+    items: Vec<Item>,
+}
+
+impl ComparableVersion {
+    pub fn new(version: &str) -> Self {
+        let mut items = vec![];
+        let mut token = String::new();
+
+        fn push_token(items: &mut Vec<Item>, token: &mut String) {
+            if !token.is_empty() {
+                items.push(match token.parse() {
+                    Ok(i) => Item::Int(i),
+                    Err(_) => Item::Qualifier(normalize_qualifier(token)),
+                });
+                token.clear();
+            }
+        }
+
+        for c in version.chars() {
+            if c == '.' || c == '-' || c == '_' {
+                push_token(&mut items, &mut token);
+                continue;
+            }
+            let switches_kind = token.chars().last().map(|last| last.is_ascii_digit() != c.is_ascii_digit()).unwrap_or(false);
+            if switches_kind {
+                push_token(&mut items, &mut token);
+            }
+            token.push(c);
+        }
+        push_token(&mut items, &mut token);
+
+        //trailing zeros and release qualifiers don't change the version
+        while let Some(last) = items.last() {
+            match *last {
+                Item::Int(0) => {}
+                Item::Qualifier(ref q) if q.is_empty() => {}
+                _ => break,
+            }
+            items.pop();
+        }
+
+        Self {
+            original: version.to_owned(),
+            items,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.original
+    }
+}
+
+impl fmt::Display for ComparableVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.original)
+    }
+}
+
+impl Ord for ComparableVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.items.len().max(other.items.len());
+        for i in 0..len {
+            let ordering = match (self.items.get(i), other.items.get(i)) {
+                (Some(Item::Int(a)), Some(Item::Int(b))) => a.cmp(b),
+                (Some(Item::Int(_)), Some(Item::Qualifier(_))) => Ordering::Greater,
+                (Some(Item::Qualifier(_)), Some(Item::Int(_))) => Ordering::Less,
+                (Some(Item::Qualifier(a)), Some(Item::Qualifier(b))) => qualifier_rank(a).cmp(&qualifier_rank(b)),
+                (Some(Item::Int(a)), None) => a.cmp(&0),
+                (None, Some(Item::Int(b))) => 0.cmp(b),
+                (Some(Item::Qualifier(a)), None) => qualifier_rank(a).cmp(&qualifier_rank("")),
+                (None, Some(Item::Qualifier(b))) => qualifier_rank("").cmp(&qualifier_rank(b)),
+                (None, None) => unreachable!("index is within the longest version"),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for ComparableVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ComparableVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ComparableVersion {}
+
+pub fn is_snapshot(version: &str) -> bool {
+    version.ends_with("-SNAPSHOT")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    version: ComparableVersion,
+    inclusive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Restriction {
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+}
+
+impl Restriction {
+    fn contains(&self, version: &ComparableVersion) -> bool {
+        let above_lower = match self.lower {
+            Some(Bound{ version: ref lower, inclusive }) => version > lower || (inclusive && version == lower),
+            None => true,
+        };
+        let below_upper = match self.upper {
+            Some(Bound{ version: ref upper, inclusive }) => version < upper || (inclusive && version == upper),
+            None => true,
+        };
+        above_lower && below_upper
+    }
+}
+
+///A maven version range, such as `[1.2,2.0)` or `(,1.0],[1.2,)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRange {
+    restrictions: Vec<Restriction>,
+}
+
+impl VersionRange {
+    pub fn parse(range: &str) -> Result<Self, Error> {
+        let bad_range = || BadVersionRange{range: range.to_owned()}.fail();
+        let mut restrictions = vec![];
+        let mut rest = range.trim();
+        while !rest.is_empty() {
+            let lower_inclusive = match rest.chars().next() {
+                Some('[') => true,
+                Some('(') => false,
+                _ => return bad_range(),
+            };
+            let end = match rest.find(|c| c == ']' || c == ')') {
+                Some(end) => end,
+                None => return bad_range(),
+            };
+            let upper_inclusive = rest[end..].starts_with(']');
+            let inner = &rest[1..end];
+            let restriction = match inner.find(',') {
+                None => {
+                    //a single exact version, like [1.0]
+                    if !(lower_inclusive && upper_inclusive) || inner.trim().is_empty() {
+                        return bad_range();
+                    }
+                    let version = ComparableVersion::new(inner.trim());
+                    Restriction{
+                        lower: Some(Bound{ version: version.clone(), inclusive: true }),
+                        upper: Some(Bound{ version, inclusive: true }),
+                    }
+                }
+                Some(comma) => {
+                    let bound = |s: &str, inclusive| {
+                        let s = s.trim();
+                        if s.is_empty() {
+                            None
+                        } else {
+                            Some(Bound{ version: ComparableVersion::new(s), inclusive })
+                        }
+                    };
+                    Restriction{
+                        lower: bound(&inner[..comma], lower_inclusive),
+                        upper: bound(&inner[comma + 1..], upper_inclusive),
+                    }
+                }
+            };
+            restrictions.push(restriction);
+            rest = rest[end + 1..].trim_start();
+            if rest.starts_with(',') {
+                rest = rest[1..].trim_start();
+            }
+        }
+        if restrictions.is_empty() {
+            return bad_range();
+        }
+        Ok(Self{ restrictions })
+    }
+
+    pub fn contains(&self, version: &ComparableVersion) -> bool {
+        self.restrictions.iter().any(|restriction| restriction.contains(version))
+    }
+
+    pub fn contains_str(&self, version: &str) -> bool {
+        self.contains(&ComparableVersion::new(version))
+    }
+}
+
+///What an artifact's version string asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionRequest {
+    Exact(String),
+    ///The newest build of a `-SNAPSHOT` version
+    Snapshot(String),
+    Range(VersionRange),
+    ///The newest version, including snapshots
+    Latest,
+    ///The newest non snapshot version
+    Release,
+}
+
+impl VersionRequest {
+    pub fn parse(version: &str) -> Result<Self, Error> {
+        Ok(match version {
+            "LATEST" => Self::Latest,
+            "RELEASE" => Self::Release,
+            v if v.starts_with('[') || v.starts_with('(') => Self::Range(VersionRange::parse(v)?),
+            v if is_snapshot(v) => Self::Snapshot(v.to_owned()),
+            v => Self::Exact(v.to_owned()),
+        })
+    }
+
+    ///Whether this needs repository metadata to turn into an exact version
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            Self::Exact(_) => false,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(s: &str) -> ComparableVersion {
+        ComparableVersion::new(s)
+    }
+
+    #[test]
+    fn orders_numerically() {
+        assert!(v("1.0") < v("1.1"));
+        assert!(v("1.9") < v("1.10"));
+        assert!(v("1.0.1") > v("1.0"));
+        assert_eq!(v("1"), v("1.0.0"));
+    }
+
+    #[test]
+    fn orders_qualifiers() {
+        assert!(v("1.0-alpha1") < v("1.0-beta"));
+        assert!(v("1.0-beta") < v("1.0-rc1"));
+        assert!(v("1.0-rc1") < v("1.0-SNAPSHOT"));
+        assert!(v("1.0-SNAPSHOT") < v("1.0"));
+        assert!(v("1.0") < v("1.0-sp1"));
+        assert!(v("1.0-sp1") < v("1.0.1"));
+        assert_eq!(v("1.0-final"), v("1.0"));
+        assert_eq!(v("1.0-CR1"), v("1.0-rc1"));
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let range = VersionRange::parse("[1.2,2.0)").unwrap();
+        assert!(range.contains_str("1.2"));
+        assert!(range.contains_str("1.9.9"));
+        assert!(!range.contains_str("2.0"));
+        assert!(!range.contains_str("1.1"));
+
+        let exact = VersionRange::parse("[1.0]").unwrap();
+        assert!(exact.contains_str("1.0"));
+        assert!(!exact.contains_str("1.0.1"));
+
+        let multiple = VersionRange::parse("(,1.0],[1.2,)").unwrap();
+        assert!(multiple.contains_str("0.5"));
+        assert!(!multiple.contains_str("1.1"));
+        assert!(multiple.contains_str("3.0"));
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        assert!(VersionRange::parse("[1.0").is_err());
+        assert!(VersionRange::parse("(1.0)").is_err());
+        assert!(VersionRange::parse("1.0]").is_err());
+    }
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(VersionRequest::parse("LATEST"), Ok(VersionRequest::Latest));
+        assert_eq!(VersionRequest::parse("RELEASE"), Ok(VersionRequest::Release));
+        assert_eq!(VersionRequest::parse("1.0-SNAPSHOT"), Ok(VersionRequest::Snapshot("1.0-SNAPSHOT".into())));
+        assert_eq!(VersionRequest::parse("1.0"), Ok(VersionRequest::Exact("1.0".into())));
+        assert!(VersionRequest::parse("[1.0,2.0)").unwrap().is_dynamic());
+    }
+}
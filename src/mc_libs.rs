@@ -1,15 +1,50 @@
 use http::Uri;
 use crate::{
+    cache::{self, Cacheable, Cache as _, FileCache},
     download::{self,Downloadable},
-    maven,
+    maven::{self, RepositoryConfig, ResolvedArtifact},
 };
 use std::{
     io::Cursor,
     path::PathBuf,
 };
 use slog::Logger;
+use snafu::{OptionExt, Snafu};
 use futures::prelude::*;
 
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("Io error {} while reading {}", source, path))]
+    Reading{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Forge jar {} is not a valid zip: {}", path, source))]
+    InvalidForgeJar{
+        path: String,
+        source: zip::result::ZipError,
+    },
+    #[snafu(display("Version json {} is in a bad format: {}", path, source))]
+    InvalidVersionJson{
+        path: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Library name {} is not a maven identifier", name))]
+    BadLibraryName{
+        name: String,
+    },
+    #[snafu(display("Library {} has an invalid url: {}", name, source))]
+    BadLibraryUrl{
+        name: String,
+        source: http::uri::InvalidUri,
+    },
+    #[snafu(display("Error while caching {}: {}", item, source))]
+    Caching{
+        item: String,
+        source: crate::cache::Error,
+    },
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum OS {
     #[serde(rename = "osx")]
@@ -75,6 +110,10 @@ pub struct MCLibraryListing {
     pub rules: Option<Vec<Rule>>,
     pub natives: Option<Natives>,
     pub extract: Option<Extract>,
+    ///The repository to fetch from, forge sets this for libraries which aren't on mojang's
+    pub url: Option<String>,
+    ///Forge sets this to false for libraries only the server needs
+    pub clientreq: Option<bool>,
 }
 
 impl MCLibraryListing {
@@ -82,13 +121,48 @@ impl MCLibraryListing {
         self.natives.is_some()
     }
 
+    ///Whether the library is used on this platform, where the last rule matching it decides
+    pub fn is_allowed(&self) -> bool {
+        match self.rules {
+            None => true,
+            Some(ref rules) => rules.iter()
+                .filter(|rule| rule.os_matches())
+                .last()
+                .map_or(false, |rule| rule.action == Action::Allow),
+        }
+    }
+
     fn platform_native_classifier(&self) -> Option<String> {
-        match ::std::env::consts::OS {
+        let classifier = match ::std::env::consts::OS {
             "windows" => self.natives.clone().and_then(|n| n.windows),
             "linux" => self.natives.clone().and_then(|n| n.linux),
             "macos" => self.natives.clone().and_then(|n| n.osx),
             _ => None,
+        };
+        let arch = if cfg!(target_pointer_width = "64") { "64" } else { "32" };
+        classifier.map(|classifier| classifier.replace("${arch}", arch))
+    }
+
+    ///The artifact this library is fetched as on this platform, or None if the client doesn't use it here
+    pub fn resolved(&self, repositories: &RepositoryConfig) -> Result<Option<ResolvedArtifact>, Error> {
+        if !self.is_allowed() || self.clientreq == Some(false) {
+            return Ok(None);
+        }
+        let mut artifact: maven::Artifact = self.name.parse().ok().context(BadLibraryName{name: self.name.clone()})?;
+        if self.is_native() {
+            match self.platform_native_classifier() {
+                Some(classifier) => artifact.classifier = Some(classifier),
+                //no natives for this platform
+                None => return Ok(None),
+            }
         }
+        let mut base = self.url.clone().unwrap_or_else(|| MC_LIBS_MAVEN.to_owned());
+        //without the slash, joining the artifact path on would replace the last part of the url
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+        let base = Uri::from_str(&base).context(BadLibraryUrl{name: self.name.clone()})?;
+        Ok(Some(artifact.resolve_with(repositories, Some(&base))))
     }
 }
 
@@ -102,29 +176,11 @@ impl Downloadable for MCLibraryListing {
         log: Logger,
     ) -> download::BoxFuture<()> {
         Box::pin(async move{
-            let resolved_artifact = if self.is_native() {
-                let mut artifact = self.name.parse::<maven::Artifact>().unwrap();
-                let disallowed = if let Some(ref rules) = self.rules {
-                    rules
-                        .iter()
-                        .any(|rule| rule.os_matches() && rule.action == Action::Disallow)
-                } else {
-                    false
-                };
-                if disallowed {
-                    None //nothing to download
-                } else {
-                    artifact.classifier = self.platform_native_classifier();
-                    let base = Uri::from_str(MC_LIBS_MAVEN).context(crate::download::error::BadUri)?;
-                    Some(artifact.resolve(base))
-                }
-            } else {
-                let artifact = self.name.parse::<maven::Artifact>().unwrap();
-                let base = Uri::from_str(MC_LIBS_MAVEN).context(crate::download::error::BadUri)?;
-                Some(artifact.resolve(base))
-            };
+            let resolved_artifact = cache::ResultExt::erased(self.resolved(manager.repositories())).context(crate::download::error::Cached)?;
             if let Some(resolved_artifact) = resolved_artifact {
                 location.push(resolved_artifact.to_path());
+                //installing goes into the folder, not the file
+                location.pop();
                 resolved_artifact.download(location, manager, log).await?;
             }
             Ok(())
@@ -132,6 +188,112 @@ impl Downloadable for MCLibraryListing {
     }
 }
 
+fn version_json_url(id: &str) -> String {
+    format!("http://s3.amazonaws.com/Minecraft.Download/versions/{0}/{0}.json", id)
+}
+
+///A minecraft version's json, as published by mojang, cached so its libraries can be listed offline
+#[derive(Debug, Clone)]
+pub struct VersionJson {
+    pub id: String,
+}
+
+impl Cacheable for VersionJson {
+    type Cache = FileCache;
+    fn cached_path(&self) -> PathBuf {
+        let mut p = app_dirs::app_dir(app_dirs::AppDataType::UserCache, crate::APP_INFO, "mc_versions").expect("Cache directory must be accesible");
+        p.push(format!("{}.json", self.id));
+        p
+    }
+    fn uri(&self) -> cache::Result<Uri> {
+        Uri::from_str(&version_json_url(&self.id)).context(cache::error::BadUri)
+    }
+}
+
+///The parts of a version json needed to find its libraries
+#[derive(Deserialize, Debug)]
+struct LibraryList {
+    #[serde(rename = "inheritsFrom")]
+    inherits_from: Option<String>,
+    libraries: Vec<MCLibraryListing>,
+}
+
+impl LibraryList {
+    ///Reads the version json from a cached forge jar
+    fn of_forge(forge: &ResolvedArtifact) -> Result<Self, Error> {
+        let path = forge.cached_path();
+        let file = std::fs::File::open(&path).context(Reading{path: path.display().to_string()})?;
+        let mut zip = zip::ZipArchive::new(file).context(InvalidForgeJar{path: path.display().to_string()})?;
+        let version_json = zip.by_name("version.json").context(InvalidForgeJar{path: path.display().to_string()})?;
+        serde_json::from_reader(version_json).context(InvalidVersionJson{path: format!("{}!version.json", path.display())})
+    }
+
+    fn of_minecraft(version: &VersionJson) -> Result<Self, Error> {
+        let path = version.cached_path();
+        let contents = std::fs::read(&path).context(Reading{path: path.display().to_string()})?;
+        serde_json::from_slice(&contents).context(InvalidVersionJson{path: path.display().to_string()})
+    }
+}
+
+///Resolves `listings` for this platform, leaving out duplicates and forge itself,
+///which is installed separately as it's published under a classifier
+fn resolve_all(listings: Vec<MCLibraryListing>, forge: &ResolvedArtifact, repositories: &RepositoryConfig) -> Result<Vec<ResolvedArtifact>, Error> {
+    let mut libraries: Vec<ResolvedArtifact> = vec![];
+    for listing in listings {
+        if let Some(library) = listing.resolved(repositories)? {
+            let is_forge = library.artifact.group == forge.artifact.group && library.artifact.artifact == forge.artifact.artifact;
+            if !is_forge && !libraries.iter().any(|existing| existing.artifact == library.artifact) {
+                libraries.push(library);
+            }
+        }
+    }
+    Ok(libraries)
+}
+
+///The minecraft version `forge` is for, read from the cached forge jar
+pub fn minecraft_version(forge: &ResolvedArtifact) -> Result<Option<VersionJson>, Error> {
+    Ok(LibraryList::of_forge(forge)?.inherits_from.map(|id| VersionJson{id}))
+}
+
+///Lists the libraries forge and minecraft need which aren't in the local cache, along with minecraft's version json if it isn't.
+///The forge jar must already be cached.
+pub fn uncached_libraries(forge: &ResolvedArtifact, repositories: &RepositoryConfig) -> Result<Vec<String>, Error> {
+    let forge_libraries = LibraryList::of_forge(forge)?;
+    let mut listings = forge_libraries.libraries;
+    let mut missing = vec![];
+    if let Some(id) = forge_libraries.inherits_from {
+        let version = VersionJson{id};
+        if version.is_cached() {
+            listings.extend(LibraryList::of_minecraft(&version)?.libraries);
+        } else {
+            missing.push(format!("minecraft {} version json", version.id));
+        }
+    }
+    missing.extend(resolve_all(listings, forge, repositories)?.into_iter()
+        .filter(|library| !library.is_cached())
+        .map(|library| format!("library {}", library.artifact)));
+    Ok(missing)
+}
+
+///Caches minecraft's version json, then every library forge and minecraft need, returning the libraries.
+///The forge jar must already be cached.
+pub async fn cache_libraries(forge: ResolvedArtifact, manager: download::Manager, log: Logger) -> Result<Vec<ResolvedArtifact>, Error> {
+    let forge_libraries = LibraryList::of_forge(&forge)?;
+    let mut listings = forge_libraries.libraries;
+    if let Some(id) = forge_libraries.inherits_from {
+        let version = VersionJson{id};
+        let item = format!("minecraft {} version json", version.id);
+        FileCache::with(version.clone(), manager.clone(), log.clone()).await.context(Caching{item})?;
+        listings.extend(LibraryList::of_minecraft(&version)?.libraries);
+    }
+    let libraries = resolve_all(listings, &forge, manager.repositories())?;
+    for library in &libraries {
+        let item = format!("library {}", library.artifact);
+        maven::Cache::with(library.clone(), manager.clone(), log.clone()).await.context(Caching{item})?;
+    }
+    Ok(libraries)
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MCVersionInfo {
     pub id: String,
@@ -160,13 +322,7 @@ use snafu::ResultExt;
 impl MCVersionInfo {
     pub fn version(ver: &str) -> impl Future<Output=crate::Result<Self>> {
         let client = hyper::Client::new();
-        let uri = Uri::from_str(
-            format!(
-                "http://s3.amazonaws.com/Minecraft.\
-                 Download/versions/{0}/{0}.json",
-                ver
-            ).as_str(),
-        ).unwrap();
+        let uri = Uri::from_str(&version_json_url(ver)).unwrap();
         async move{
             let res = client.get(uri).await.context(error::Http)?;
             let buf = res.into_body().map_ok(hyper::Chunk::into_bytes).try_concat().await.context(error::Http)?;
@@ -174,4 +330,32 @@ impl MCVersionInfo {
             Ok(info)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn listing(json: serde_json::Value) -> MCLibraryListing {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn resolves_forge_libraries_on_their_own_repo() {
+        let repositories = RepositoryConfig::default();
+        let scala = listing(json!({"name": "org.scala-lang:scala-library:2.11.1", "url": "http://files.minecraftforge.net/maven", "clientreq": true}));
+        let resolved = scala.resolved(&repositories).unwrap().unwrap();
+        assert_eq!(resolved.repos.first().map(Uri::to_string), Some("http://files.minecraftforge.net/maven/".to_owned()));
+        let server_only = listing(json!({"name": "java3d:vecmath:1.5.2", "clientreq": false}));
+        assert!(server_only.resolved(&repositories).unwrap().is_none());
+    }
+
+    #[test]
+    fn last_matching_rule_decides() {
+        let os = if cfg!(windows) { "windows" } else if cfg!(target_os = "macos") { "osx" } else { "linux" };
+        let only_here = listing(json!({"name": "a:b:1", "rules": [{"action": "allow", "os": {"name": os}}]}));
+        let except_here = listing(json!({"name": "a:b:1", "rules": [{"action": "allow"}, {"action": "disallow", "os": {"name": os}}]}));
+        assert!(only_here.is_allowed());
+        assert!(!except_here.is_allowed());
+    }
+}
@@ -1,34 +1,85 @@
 use crate::{
-    cache::Cache,
+    cache::{Cache, Cacheable},
     curseforge,
     download::{self,Downloadable},
     forge_version,
-    maven::{self, ResolvedArtifact},
+    pack_edit::PackChange,
+    pack_format::PackFormat,
+    pack_overlay::PackOverlay,
+    maven::{self, RepositoryConfig, ResolvedArtifact, version::VersionRange},
+    optional_mods::{self, Choices},
     error::prelude::*,
 };
 use futures::prelude::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use http::{self, Uri};
+use url::Url;
 use slog::Logger;
 use std::{
-    path::PathBuf,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 use semver;
+use schemars::JsonSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq, JsonSchema)]
 pub enum ModSource {
     CurseforgeMod(curseforge::Mod),
     MavenMod {
         repo: String,
         artifact: maven::Artifact,
+        ///The exact version a dynamic `artifact.version` (range, `LATEST`, `RELEASE` or snapshot) was last resolved to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        resolved_version: Option<String>,
     },
 }
 
+///The exact artifact to install for a maven mod, or None if its dynamic version hasn't been resolved yet
+fn exact_artifact(artifact: &maven::Artifact, resolved_version: &Option<String>) -> Option<maven::Artifact> {
+    match *resolved_version {
+        Some(ref version) => Some(maven::Artifact{
+            version: version.clone(),
+            ..artifact.clone()
+        }),
+        None => match maven::version::VersionRequest::parse(&artifact.version) {
+            Ok(ref request) if !request.is_dynamic() => Some(artifact.clone()),
+            _ => None,
+        },
+    }
+}
+
+///Resolves a maven mod onto the manager's repositories, resolving its version first if that hasn't been done already
+async fn resolve_maven_mod(
+    repo: String,
+    artifact: maven::Artifact,
+    resolved_version: Option<String>,
+    manager: download::Manager,
+    log: Logger,
+) -> download::Result<ResolvedArtifact> {
+    let repo = Uri::from_str(repo.as_str()).context(crate::download::error::BadUri)?;
+    let repos = manager.repositories().repos_for(&artifact.group, Some(&repo));
+    let artifact = match exact_artifact(&artifact, &resolved_version) {
+        Some(artifact) => artifact,
+        None => {
+            warn!(log, "maven mod version has not been resolved, resolving now"; "artifact"=>artifact.to_string());
+            let version = artifact.clone().resolve_version(repos.clone(), manager.clone()).await.context(crate::download::error::Maven)?;
+            maven::Artifact{
+                version,
+                ..artifact
+            }
+        }
+    };
+    Ok(artifact.resolve_on(repos))
+}
+
 impl ModSource {
     pub fn version_string(&self) -> String {
         match *self {
             Self::CurseforgeMod(ref modd) => modd.version.to_string(),
-            Self::MavenMod { ref artifact, .. } => artifact.version.to_string(),
+            Self::MavenMod { ref artifact, ref resolved_version, .. } => {
+                resolved_version.clone().unwrap_or_else(|| artifact.version.clone())
+            }
         }
     }
     pub fn identifier_string(&self) -> String {
@@ -37,6 +88,28 @@ impl ModSource {
             Self::MavenMod { ref artifact, .. } => artifact.to_string(),
         }
     }
+    ///Identifies the mod regardless of which version of it this is
+    pub fn key(&self) -> String {
+        match *self {
+            Self::CurseforgeMod(ref modd) => modd.id.clone(),
+            Self::MavenMod { ref artifact, .. } => {
+                match artifact.classifier {
+                    Some(ref classifier) => format!("{}:{}:{}", artifact.group, artifact.artifact, classifier),
+                    None => format!("{}:{}", artifact.group, artifact.artifact),
+                }
+            }
+        }
+    }
+    ///Orders two versions of the same mod, or None if they aren't the same mod
+    pub fn compare_versions(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::CurseforgeMod(ref a), Self::CurseforgeMod(ref b)) if a.id == b.id => Some(a.version.cmp(&b.version)),
+            (a @ Self::MavenMod { .. }, b @ Self::MavenMod { .. }) if a.key() == b.key() => {
+                Some(maven::version::ComparableVersion::new(&a.version_string()).cmp(&maven::version::ComparableVersion::new(&b.version_string())))
+            }
+            _ => None,
+        }
+    }
     pub fn guess_project_url(&self) -> Option<String> {
         match *self {
             Self::CurseforgeMod(ref modd) => {
@@ -45,6 +118,57 @@ impl ModSource {
             Self::MavenMod { .. } => None,
         }
     }
+    pub fn is_cached(&self) -> bool {
+        match *self {
+            Self::CurseforgeMod(ref modd) => modd.is_cached(),
+            Self::MavenMod { ref repo, ref artifact, ref resolved_version } => {
+                match (Uri::from_str(repo.as_str()), exact_artifact(artifact, resolved_version)) {
+                    (Ok(repo), Some(artifact)) => artifact.resolve(repo).is_cached(),
+                    _ => false,
+                }
+            }
+        }
+    }
+    pub fn describe(&self) -> String {
+        match *self {
+            Self::CurseforgeMod(ref modd) => format!("curseforge mod {} (file {})", modd.id, modd.version),
+            Self::MavenMod { ref repo, ref artifact, ref resolved_version } => {
+                match *resolved_version {
+                    Some(ref version) => format!("maven mod {} resolved to {} (from {})", artifact.to_string(), version, repo),
+                    None => format!("maven mod {} (from {})", artifact.to_string(), repo),
+                }
+            }
+        }
+    }
+    ///This mod with a dynamic maven version resolved to the exact version it means now, so what's installed can be recorded
+    pub async fn with_resolved_version(self, manager: download::Manager) -> download::Result<Self> {
+        match self {
+            Self::MavenMod { repo, artifact, resolved_version: None } => {
+                if exact_artifact(&artifact, &None).is_some() {
+                    return Ok(Self::MavenMod { repo, artifact, resolved_version: None });
+                }
+                let repo_uri = Uri::from_str(repo.as_str()).context(crate::download::error::BadUri)?;
+                let repos = manager.repositories().repos_for(&artifact.group, Some(&repo_uri));
+                let version = artifact.clone().resolve_version(repos, manager).await.context(crate::download::error::Maven)?;
+                Ok(Self::MavenMod { repo, artifact, resolved_version: Some(version) })
+            }
+            other => Ok(other),
+        }
+    }
+    ///Fetches this mod into the local cache without installing it anywhere, returning the cached file
+    pub fn cache(self, manager: download::Manager, log: Logger) -> download::BoxFuture<PathBuf> {
+        Box::pin(async move{
+            Ok(match self {
+                Self::CurseforgeMod(modd) => {
+                    curseforge::Cache::with(modd, manager, log).await.context(crate::download::error::Cached)?
+                }
+                Self::MavenMod { repo, artifact, resolved_version } => {
+                    let resolved = resolve_maven_mod(repo, artifact, resolved_version, manager.clone(), log.clone()).await?;
+                    maven::Cache::with(resolved, manager, log).await.context(crate::download::error::Cached)?
+                }
+            })
+        })
+    }
 }
 
 impl Downloadable for ModSource {
@@ -59,9 +183,9 @@ impl Downloadable for ModSource {
                 Self::CurseforgeMod(modd) => {
                     curseforge::Cache::install_at(modd, location, manager, log).await.context(crate::download::error::Cached)?;
                 }
-                Self::MavenMod { repo, artifact } => {
-                    let repo = Uri::from_str(repo.as_str()).context(crate::download::error::BadUri)?;
-                    artifact.download_from(location.as_ref(), repo, manager, log).await.context(crate::download::error::Cached)?;
+                Self::MavenMod { repo, artifact, resolved_version } => {
+                    let resolved = resolve_maven_mod(repo, artifact, resolved_version, manager.clone(), log.clone()).await?;
+                    maven::Cache::install_at(resolved, location, manager, log).await.context(crate::download::error::Cached)?;
                 }
             }
             Ok(())
@@ -69,106 +193,576 @@ impl Downloadable for ModSource {
     }
 }
 
-pub type ModList = Vec<ModSource>;
+///Which side of the game a mod is needed on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Both,
+    Client,
+    Server,
+}
+
+impl Default for Side {
+    fn default() -> Self {
+        Side::Both
+    }
+}
+
+impl Side {
+    pub fn is_both(&self) -> bool {
+        *self == Side::Both
+    }
+    pub fn on_client(self) -> bool {
+        self != Side::Server
+    }
+    pub fn value(self) -> &'static str {
+        match self {
+            Side::Both => "both",
+            Side::Client => "client",
+            Side::Server => "server",
+        }
+    }
+}
+
+///A maven style version range a mod must stay within when upgraded, such as `[1.2,2.0)`.
+///Curseforge file ids are checked against it as plain numbers.
+#[derive(Debug, Clone)]
+pub struct VersionConstraint {
+    raw: String,
+    range: VersionRange,
+}
+
+impl VersionConstraint {
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn allows(&self, version: &str) -> bool {
+        self.range.contains_str(version)
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = maven::version::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self{
+            raw: s.to_owned(),
+            range: VersionRange::parse(s)?,
+        })
+    }
+}
+
+impl std::fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl PartialEq for VersionConstraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for VersionConstraint {}
+
+impl std::hash::Hash for VersionConstraint {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
+}
+
+impl serde::Serialize for VersionConstraint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for VersionConstraint {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = <String as serde::Deserialize>::deserialize(deserializer)?;
+        raw.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+///A mod in a pack: where it comes from, plus anything the pack says about it
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq, JsonSchema)]
+pub struct ModEntry {
+    #[serde(flatten)]
+    pub source: ModSource,
+    #[serde(default, skip_serializing_if = "Side::is_both")]
+    pub side: Side,
+    ///Never changed by upgrades
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool,
+    ///The least stable release this mod may be upgraded to, in place of the pack's `auto_update_release_status`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_status: Option<curseforge::ReleaseStatus>,
+    ///The versions upgrades may pick for this mod
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub constraint: Option<VersionConstraint>,
+    ///Players choose whether to install this mod, which they don't by default
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub optional: bool,
+    ///What an optional mod adds, shown when choosing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    ///Optional mods in the same group, such as `shaders`, are chosen together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+impl ModEntry {
+    ///The release status to look for upgrades with, given the pack's
+    pub fn upgrade_release_status(&self, pack_status: curseforge::ReleaseStatus) -> curseforge::ReleaseStatus {
+        self.release_status.unwrap_or(pack_status)
+    }
+
+    ///Whether an upgrade may move this mod to `version`
+    pub fn allows_version(&self, version: &str) -> bool {
+        self.constraint.as_ref().map_or(true, |constraint| constraint.allows(version))
+    }
+}
+
+impl From<ModSource> for ModEntry {
+    fn from(source: ModSource) -> Self {
+        Self{
+            source,
+            side: Side::default(),
+            pinned: false,
+            release_status: None,
+            constraint: None,
+            optional: false,
+            description: None,
+            group: None,
+        }
+    }
+}
+
+impl std::ops::Deref for ModEntry {
+    type Target = ModSource;
+    fn deref(&self) -> &ModSource {
+        &self.source
+    }
+}
+
+pub type ModList = Vec<ModEntry>;
+
+fn default_channel() -> String {
+    "stable".into()
+}
+
+///Release channels for a pack, each pointing at the url of the pack on that channel
+#[derive(Deserialize, Debug, Clone)]
+pub struct PackChannels{
+    pub channels: BTreeMap<String, String>,
+    ///The channel used when none is asked for
+    #[serde(default = "default_channel")]
+    pub default: String,
+    ///Urls for specific pack versions, for pinning to
+    #[serde(default)]
+    pub versions: BTreeMap<String, String>,
+}
+
+///Which release of an indirected pack to install
+#[derive(Debug, Clone, Default)]
+pub struct PackSelector{
+    pub channel: Option<String>,
+    pub version: Option<semver::Version>,
+}
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum IndirectableModpack{
     Real(ModpackConfig),
+    Overlay(PackOverlay),
+    Channels(PackChannels),
     Indirected(String),
 }
 
+///Reads the pack at `location`, which may be a local file or a http(s) url
+async fn fetch_pack(location: Url, offline: bool) -> Result<IndirectableModpack,crate::Error>{
+    let format = PackFormat::for_path(Path::new(location.path()));
+    let contents = if location.scheme() == "file" {
+        let path = location.to_file_path().ok().context(BadPackLocation{location: location.to_string()}).erased()?;
+        read_pack_file(&path).await?
+    } else if offline {
+        return IndirectedWhileOffline{uri: location.to_string()}.fail().erased();
+    } else {
+        let uri = crate::util::url_to_uri(&location).context(error::Uri)?;
+        let (res,_url) = crate::download::HttpSimple::new()
+            .get_following_redirects(uri)
+            .context(error::Download)?
+            .await
+            .context(error::Download)?;
+        let data = res
+            .into_body()
+            .map_ok(hyper::Chunk::into_bytes).try_concat().await
+            .context(error::Http)?;
+        String::from_utf8_lossy(&data).into_owned()
+    };
+    IndirectableModpack::parse(format, &contents).context(FetchedPack{uri: location.to_string()}).erased()
+}
+
+///Reads and resolves the pack `reference` points at, which is relative to the innermost pack of `chain`
+async fn follow(chain: Vec<Url>, reference: String, offline: bool) -> Result<ModpackConfig,crate::Error>{
+    let location = match chain.last() {
+        Some(current) => current.join(&reference),
+        None => Url::parse(&reference),
+    }.context(BadPackReference{reference: reference.clone()}).erased()?;
+    if chain.contains(&location) {
+        return ExtendsCycle{location: location.to_string()}.fail().erased();
+    }
+    let pack = fetch_pack(location.clone(), offline).await?;
+    let mut chain = chain;
+    chain.push(location);
+    pack.resolve_in(chain, offline, PackSelector::default()).await
+}
+
+///The keys of an overlay, which a real pack must not have
+const OVERLAY_KEYS: &[&str] = &["extends", "add", "remove", "override"];
+
 impl IndirectableModpack{
+    ///Parses any kind of pack file. Untagged enums can only say that nothing matched,
+    ///so something that looks like a real pack is parsed as one again to find which field is wrong.
+    ///A real pack with overlay keys is rejected, since they would otherwise be silently dropped.
+    pub fn parse(format: PackFormat, s: &str) -> Result<Self,crate::pack_format::Error>{
+        let value = format.parse::<serde_json::Value>(s).ok();
+        if let Some(ref value) = value {
+            if value.get("mods").is_some() {
+                if let Some(key) = OVERLAY_KEYS.iter().find(|key| value.get(**key).is_some()) {
+                    return Err(crate::pack_format::Error::OverlayKeyInRealPack{key: (*key).to_owned()});
+                }
+            }
+        }
+        format.parse(s).or_else(|e| match value {
+            Some(ref value) if value.get("mods").is_some() => format.parse(s).map(IndirectableModpack::Real),
+            _ => Err(e),
+        })
+    }
+
     pub async fn resolve(self) -> Result<ModpackConfig,crate::Error>{
-        match self{
-            IndirectableModpack::Indirected(uri_str) => {
-                let uri = Uri::from_str(&uri_str).context(error::Uri)?;
-                let (res,_url) = crate::download::HttpSimple::new()
-                    .get_following_redirects(uri)
-                    .context(error::Download)?
-                    .await
-                    .context(error::Download)?;
-                let data = res
-                    .into_body()
-                    .map_ok(hyper::Chunk::into_bytes).try_concat().await
-                    .context(error::Http)?;
-                Ok(serde_json::from_reader(std::io::Cursor::new(data)).context(error::Json)?)
-            },
-            IndirectableModpack::Real(modpack) => Ok(modpack),
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
+        self.resolve_with(PackSelector::default()).await
+    }
+
+    ///Resolves a pack which wasn't read from anywhere in particular, so anything it points at must be a full url
+    pub async fn resolve_with(self, selector: PackSelector) -> Result<ModpackConfig,crate::Error>{
+        self.resolve_in(vec![], false, selector).await
+    }
+
+    ///Follows indirections, channels and `extends` until there's a real pack.
+    ///`chain` is where this pack and the packs that led to it were read from, outermost first,
+    ///so relative references can be followed and packs which extend themselves caught.
+    fn resolve_in(self, chain: Vec<Url>, offline: bool, selector: PackSelector) -> crate::BoxFuture<ModpackConfig>{
+        async move {
+            let pack = match self{
+                IndirectableModpack::Indirected(uri_str) => follow(chain, uri_str, offline).await?,
+                IndirectableModpack::Channels(channels) => {
+                    let uri_str = match selector.version {
+                        Some(ref version) => channels.versions.get(&version.to_string())
+                            .cloned()
+                            .context(UnknownPackVersion{version: version.to_string()})
+                            .erased()?,
+                        None => {
+                            let channel = selector.channel.clone().unwrap_or(channels.default);
+                            channels.channels.get(&channel)
+                                .cloned()
+                                .with_context(|| UnknownChannel{
+                                    channel: channel.clone(),
+                                    available: channels.channels.keys().cloned().collect::<Vec<_>>().join(", "),
+                                })
+                                .erased()?
+                        }
+                    };
+                    follow(chain, uri_str, offline).await?
+                },
+                IndirectableModpack::Overlay(overlay) => {
+                    let base = follow(chain, overlay.extends.clone(), offline).await?;
+                    overlay.apply(base).context(Extending).erased()?
+                },
+                IndirectableModpack::Real(modpack) => modpack,
+            };
+            if let Some(version) = selector.version {
+                if pack.pack_version.as_ref() != Some(&version) {
+                    return UnknownPackVersion{version: version.to_string()}.fail().erased();
+                }
+            }
+            Ok(pack)
+        }.boxed()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionBump{
+    Major,
+    Minor,
+    Patch,
+}
+
+impl FromStr for VersionBump{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "major" => Ok(Self::Major),
+            "minor" => Ok(Self::Minor),
+            "patch" => Ok(Self::Patch),
+            s => Err(format!("Unknown version bump `{}`, expected major, minor or patch", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct ChangelogEntry{
+    #[schemars(with = "String")]
+    pub version: semver::Version,
+    pub date: String,
+    pub changes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 pub struct ModpackConfig {
+    ///The minecraft versions the pack is for
+    #[schemars(with = "String")]
     pub version: semver::VersionReq,
     pub name: String,
     pub forge: String,
     pub auto_update_release_status: Option<curseforge::ReleaseStatus>,
     pub mods: ModList,
     pub icon: Option<String>,
+    ///The version of the pack itself, not to be confused with `version`, which is the minecraft version it's for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub pack_version: Option<semver::Version>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changelog: Vec<ChangelogEntry>,
+    ///Extra maven repositories for this pack, tried before the global ones
+    #[serde(default, skip_serializing_if = "RepositoryConfig::is_empty")]
+    pub repositories: RepositoryConfig,
 }
 
 impl ModpackConfig {
+    ///A minecraft 1.12.2 pack with just `mods`, for tests to change as they need
+    #[cfg(test)]
+    pub(crate) fn for_test(mods: ModList) -> Self {
+        Self{
+            version: semver::VersionReq::parse("~1.12.2").unwrap(),
+            name: "Test Pack".into(),
+            forge: "1.12.2-14.23.5.2838".into(),
+            auto_update_release_status: None,
+            mods,
+            icon: None,
+            pack_version: None,
+            changelog: vec![],
+            repositories: RepositoryConfig::default(),
+        }
+    }
     pub fn folder(&self) -> String {
         self.name.replace(|c: char| !c.is_alphanumeric(), "_")
     }
+    ///Bumps the pack version and records what changed in the changelog, starting from 0.0.0 for unversioned packs
+    pub fn bump_version(&mut self, bump: VersionBump, changes: Vec<String>) -> semver::Version {
+        let mut version = self.pack_version.clone().unwrap_or_else(|| semver::Version::new(0, 0, 0));
+        match bump {
+            VersionBump::Major => version.increment_major(),
+            VersionBump::Minor => version.increment_minor(),
+            VersionBump::Patch => version.increment_patch(),
+        }
+        self.pack_version = Some(version.clone());
+        self.changelog.push(ChangelogEntry{
+            version: version.clone(),
+            date: chrono::Local::today().format("%Y-%m-%d").to_string(),
+            changes,
+        });
+        version
+    }
+    ///Bumps the pack version only if the pack is already versioned and something changed, so unversioned packs stay that way until `dev bump` is used
+    pub fn bump_version_if_versioned(&mut self, bump: VersionBump, changes: Vec<String>) -> Option<semver::Version> {
+        if self.pack_version.is_some() && !changes.is_empty() {
+            Some(self.bump_version(bump, changes))
+        } else {
+            None
+        }
+    }
+    ///The pack's own repositories, followed by the global ones
+    pub fn repository_config(&self) -> RepositoryConfig {
+        self.repositories.merged_with(RepositoryConfig::global())
+    }
     pub fn forge_maven_artifact(&self) -> ResolvedArtifact {
+        let forge_repo = Uri::from_str(forge_version::BASE_URL).expect("const Uri should always be valid");
         maven::Artifact {
             group: "net.minecraftforge".into(),
             artifact: "forge".into(),
             version: self.forge.clone(),
             classifier: Some("universal".into()),
             extension: Some("jar".into()),
-        }.resolve(Uri::from_str(forge_version::BASE_URL).expect("const Uri should always be valid"))
-    }
-    pub fn replace_mod(&mut self, modsource: ModSource) {
-        match modsource {
-            ModSource::CurseforgeMod(curseforge::Mod { ref id, .. }) => {
-                let new_id = id;
-                let mut old_mods = vec![];
-                ::std::mem::swap(&mut self.mods, &mut old_mods);
-                self.mods = old_mods
-                    .into_iter()
-                    .filter(|source| match *source {
-                        ModSource::CurseforgeMod(curseforge::Mod {
-                            ref id,
-                            ref version,
-                        }) => {
-                            if id == new_id {
-                                println!("removing old version ({})", version);
-                                false
-                            } else {
-                                true
-                            }
-                        }
-                        _ => true,
-                    })
-                    .collect();
+        }.resolve_with(&self.repository_config(), Some(&forge_repo))
+    }
+    ///The mods installed by `update` with the optional mods in `choices`, which leaves out server only mods
+    pub fn client_mods<'a>(&'a self, choices: &'a Choices) -> impl Iterator<Item=&'a ModSource> {
+        self.mods.iter()
+            .filter(move |entry| entry.side.on_client() && optional_mods::is_chosen(entry, choices))
+            .map(|entry| &entry.source)
+    }
+    ///Lists everything needed to install this pack with the optional mods in `choices` which is not yet in the local cache
+    pub fn uncached_items(&self, choices: &Choices) -> Vec<String> {
+        let mut missing: Vec<String> = self.client_mods(choices)
+            .filter(|modd| !modd.is_cached())
+            .map(ModSource::describe)
+            .collect();
+        let forge = self.forge_maven_artifact();
+        if !forge.is_cached() {
+            //which libraries are needed can't be known until forge is cached
+            missing.push(format!("forge {}", forge.artifact.to_string()));
+        } else {
+            match crate::mc_libs::uncached_libraries(&forge, &self.repository_config()) {
+                Ok(libraries) => missing.extend(libraries),
+                Err(e) => missing.push(format!("the libraries forge {} needs ({})", self.forge, e)),
             }
-            _ => panic!("Other mod sources not yet supported"),
         }
-
-        println!("Adding: {:?}", modsource);
-
-        self.mods.push(modsource);
+        missing
+    }
+    ///Sorts mods by key then version, with exact duplicates removed
+    pub fn canonicalize(&mut self) {
+        self.mods.sort_by(|a, b| {
+            a.key().cmp(&b.key())
+                .then_with(|| a.compare_versions(b).unwrap_or(std::cmp::Ordering::Equal))
+                .then_with(|| a.version_string().cmp(&b.version_string()))
+        });
+        self.mods.dedup();
+    }
+    ///Normalizes a freshly resolved pack, dropping any duplicate mods
+    fn normalized(mut self) -> Self {
+        self.normalize();
+        self
+    }
+    ///Loads a pack in the format its extension says
+    pub async fn load(path: &Path) -> Result<ModpackConfig,crate::Error>{
+        Ok(Self::load_normalizing(path).await?.0)
+    }
+    ///Like `load`, but also returns the duplicate mods normalizing dropped, for commands which save the pack back
+    pub async fn load_normalizing(path: &Path) -> Result<(ModpackConfig, Vec<PackChange>),crate::Error>{
+        let contents = read_pack_file(path).await?;
+        let mut pack: ModpackConfig = PackFormat::for_path(path).parse(&contents).context(ReadingPack{path: path.display().to_string()}).erased()?;
+        let dropped = pack.normalize();
+        Ok((pack, dropped))
+    }
+    ///Saves the pack normalized, so the file diffs and merges cleanly, in the format the extension of `path` says.
+    ///Only the comment block at the top of a toml or yaml file is kept, comments anywhere else are lost.
+    pub async fn save(&self, path: &Path) -> Result<(),crate::Error>{
+        let mut pack = self.clone();
+        pack.normalize();
+        let format = PackFormat::for_path(path);
+        let previous = read_pack_file(path).await.ok();
+        let contents = format.to_string(&pack).context(WritingPack{path: path.display().to_string()}).erased()?;
+        let contents = format.with_header(previous.as_ref().map(String::as_str), contents);
+        let mut file = tokio::fs::File::create(path).await.context(CreatingPack{path: path.display().to_string()}).erased()?;
+        file.write_all(contents.as_bytes()).await.context(CreatingPack{path: path.display().to_string()}).erased()
+    }
+    ///Loads a pack which may be indirected, have release channels, or extend another pack
+    pub async fn load_maybe_indirected(path: &Path) -> Result<ModpackConfig,crate::Error>{
+        Self::load_maybe_indirected_with(path, PackSelector::default()).await
     }
-    pub fn add_mod_by_url(&mut self, mod_url: &str) -> crate::Result<()> {
-        let modsource: ModSource = curseforge::Mod::from_url(mod_url)?.into();
-        self.replace_mod(modsource);
-        Ok(())
+    pub async fn load_maybe_indirected_with(path: &Path, selector: PackSelector) -> Result<ModpackConfig,crate::Error>{
+        let (location, indirectable) = read_indirectable(path).await?;
+        Ok(indirectable.resolve_in(vec![location], false, selector).await?.normalized())
     }
-    pub async fn load_maybe_indirected(file: &mut tokio::fs::File) -> Result<ModpackConfig,crate::Error>{
-        let indirectable: IndirectableModpack = crate::async_json::read(file).await.context(NotAValidIndirectableModpack).erased()?;
-        Ok(indirectable.resolve().await?)
+    ///Like `load_maybe_indirected`, but without normalizing, so the pack can be checked as written.
+    ///Only packs pointing at local files can be followed when `offline`.
+    pub async fn load_unnormalized(path: &Path, offline: bool) -> Result<ModpackConfig,crate::Error>{
+        let (location, indirectable) = read_indirectable(path).await?;
+        indirectable.resolve_in(vec![location], offline, PackSelector::default()).await
     }
+    ///Like `load_maybe_indirected`, but never touches the network, so only packs pointing at local files can be followed
+    pub async fn load_offline(path: &Path) -> Result<ModpackConfig,crate::Error>{
+        Self::load_offline_with(path, PackSelector::default()).await
+    }
+    pub async fn load_offline_with(path: &Path, selector: PackSelector) -> Result<ModpackConfig,crate::Error>{
+        let (location, indirectable) = read_indirectable(path).await?;
+        Ok(indirectable.resolve_in(vec![location], true, selector).await?.normalized())
+    }
+}
+
+async fn read_pack_file(path: &Path) -> Result<String,crate::Error>{
+    let mut file = tokio::fs::File::open(path).await.context(OpeningPack{path: path.display().to_string()}).erased()?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.context(OpeningPack{path: path.display().to_string()}).erased()?;
+    Ok(contents)
+}
+
+///Reads the pack file at `path`, along with where it is as a url for anything it points at to be relative to
+async fn read_indirectable(path: &Path) -> Result<(Url, IndirectableModpack),crate::Error>{
+    let contents = read_pack_file(path).await?;
+    let absolute = std::fs::canonicalize(path).context(OpeningPack{path: path.display().to_string()}).erased()?;
+    let location = Url::from_file_path(&absolute).ok().context(BadPackLocation{location: path.display().to_string()}).erased()?;
+    let indirectable = IndirectableModpack::parse(PackFormat::for_path(path), &contents).context(NotAValidIndirectableModpack).erased()?;
+    Ok((location, indirectable))
 }
 
 use snafu::Snafu;
 #[derive(Debug,Snafu)]
 pub enum Error{
-    #[snafu(display("not a valid (indirectable) modpack config"))]
+    #[snafu(display("not a valid (indirectable) modpack config: {}", source))]
     NotAValidIndirectableModpack{
-        source: crate::async_json::Error,
-    }
+        source: crate::pack_format::Error,
+    },
+    #[snafu(display("pack {} could not be opened: {}", path, source))]
+    OpeningPack{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("pack file {} is in bad format: {}", path, source))]
+    ReadingPack{
+        path: String,
+        source: crate::pack_format::Error,
+    },
+    #[snafu(display("pack file {} could not be created: {}", path, source))]
+    CreatingPack{
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("error while writing pack file {}: {}", path, source))]
+    WritingPack{
+        path: String,
+        source: crate::pack_format::Error,
+    },
+    #[snafu(display("pack fetched from {} is in bad format: {}", uri, source))]
+    FetchedPack{
+        uri: String,
+        source: crate::pack_format::Error,
+    },
+    #[snafu(display("no channel named {} (available channels: {})", channel, available))]
+    UnknownChannel{
+        channel: String,
+        available: String,
+    },
+    #[snafu(display("pack version {} is not available", version))]
+    UnknownPackVersion{
+        version: String,
+    },
+    #[snafu(display("pack is indirected to {}, which can't be fetched in offline mode", uri))]
+    IndirectedWhileOffline{
+        uri: String,
+    },
+    #[snafu(display("{} is not a valid pack location", location))]
+    BadPackLocation{
+        location: String,
+    },
+    #[snafu(display("can't follow {} to another pack: {}", reference, source))]
+    BadPackReference{
+        reference: String,
+        source: url::ParseError,
+    },
+    #[snafu(display("pack {} extends itself", location))]
+    ExtendsCycle{
+        location: String,
+    },
+    #[snafu(display("can't extend the base pack: {}", source))]
+    Extending{
+        source: crate::pack_overlay::Error,
+    },
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+//! Optional mods, which players choose whether to install, either one at a time or as a group
+use crate::{
+    mod_source::ModEntry,
+    prompt::{self, prompt_yes_no, Response},
+};
+use snafu::Snafu;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("no optional mod or group named {} (available: {})", name, available))]
+    UnknownFeature{
+        name: String,
+        available: String,
+    },
+}
+
+///Whether each feature is installed, by name
+pub type Choices = BTreeMap<String, bool>;
+
+///How to choose optional mods when installing a pack
+#[derive(Debug, Clone, Default)]
+pub struct Selection{
+    ///Features to install, on top of those chosen before
+    pub with: Vec<String>,
+    ///Features to leave out, on top of those chosen before
+    pub without: Vec<String>,
+    ///Whether to ask about features which haven't been chosen yet, rather than leaving them out
+    pub ask: bool,
+}
+
+///Something a player can choose to install: a group of optional mods, or an optional mod outside any group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feature{
+    pub name: String,
+    pub descriptions: Vec<String>,
+    ///The keys of the mods it installs
+    pub mods: Vec<String>,
+}
+
+impl std::fmt::Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.descriptions.is_empty() {
+            write!(f, ": {}", self.descriptions.join(" "))?;
+        }
+        if self.mods.len() > 1 || self.mods.first() != Some(&self.name) {
+            write!(f, " ({})", self.mods.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+///The name of the feature `entry` is installed as part of, or None if it isn't optional
+pub fn feature_of(entry: &ModEntry) -> Option<String> {
+    if entry.optional {
+        Some(entry.group.clone().unwrap_or_else(|| entry.key()))
+    } else {
+        None
+    }
+}
+
+pub fn features(mods: &[ModEntry]) -> Vec<Feature> {
+    let mut features: BTreeMap<String, Feature> = BTreeMap::new();
+    for entry in mods {
+        if let Some(name) = feature_of(entry) {
+            let feature = features.entry(name.clone()).or_insert_with(|| Feature{
+                name,
+                descriptions: vec![],
+                mods: vec![],
+            });
+            feature.mods.push(entry.key());
+            feature.descriptions.extend(entry.description.clone());
+        }
+    }
+    features.into_iter().map(|(_, feature)| feature).collect()
+}
+
+///Records the features asked for with `--with` and `--without` over the remembered choices
+pub fn choose(choices: &mut Choices, features: &[Feature], with: &[String], without: &[String]) -> Result<(), Error> {
+    for &(names, chosen) in &[(with, true), (without, false)] {
+        for name in names {
+            if !features.iter().any(|feature| feature.name == *name) {
+                return UnknownFeature{
+                    name: name.clone(),
+                    available: features.iter().map(|feature| feature.name.clone()).collect::<Vec<_>>().join(", "),
+                }.fail();
+            }
+            choices.insert(name.clone(), chosen);
+        }
+    }
+    Ok(())
+}
+
+///Asks whether to install each feature there's no choice for yet.
+///Nothing is asked when stdin isn't a terminal, so those features are left out
+pub fn ask_undecided(choices: &mut Choices, features: &[Feature]) {
+    if !prompt::is_interactive() {
+        return;
+    }
+    for feature in features.iter().filter(|feature| !choices.contains_key(&feature.name)) {
+        let chosen = prompt_yes_no(&format!("Install optional {}? ", feature), Response::No) == Response::Yes;
+        choices.insert(feature.name.clone(), chosen);
+    }
+}
+
+///Whether `entry` is installed, leaving out optional mods nobody has chosen
+pub fn is_chosen(entry: &ModEntry, choices: &Choices) -> bool {
+    feature_of(entry).map_or(true, |name| choices.get(&name).cloned().unwrap_or(false))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curseforge, mod_source::ModSource};
+
+    fn optional(id: &str, group: Option<&str>) -> ModEntry {
+        ModEntry{
+            optional: true,
+            group: group.map(str::to_owned),
+            ..ModSource::CurseforgeMod(curseforge::Mod{ id: id.into(), version: 1 }).into()
+        }
+    }
+
+    #[test]
+    fn groups_optional_mods_into_features() {
+        let jei: ModEntry = ModSource::CurseforgeMod(curseforge::Mod{ id: "jei".into(), version: 1 }).into();
+        let mods = vec![jei, optional("optifine", Some("shaders")), optional("shaders-mod", Some("shaders")), optional("journeymap", None)];
+        let names: Vec<_> = features(&mods).into_iter().map(|feature| (feature.name, feature.mods.len())).collect();
+        assert_eq!(names, vec![("journeymap".to_owned(), 1), ("shaders".to_owned(), 2)]);
+    }
+
+    #[test]
+    fn flags_override_remembered_choices() {
+        let mods = vec![optional("optifine", Some("shaders")), optional("journeymap", None)];
+        let features = features(&mods);
+        let mut choices: Choices = vec![("shaders".to_owned(), true)].into_iter().collect();
+        choose(&mut choices, &features, &["journeymap".to_owned()], &["shaders".to_owned()]).unwrap();
+        assert!(!is_chosen(&mods[0], &choices));
+        assert!(is_chosen(&mods[1], &choices));
+        assert!(choose(&mut choices, &features, &["minimap".to_owned()], &[]).is_err());
+    }
+}
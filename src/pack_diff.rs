@@ -0,0 +1,114 @@
+//! What changed between two versions of a pack
+use crate::{
+    curseforge::ReleaseStatus,
+    mod_source::{ModEntry, ModpackConfig},
+};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModUpdate {
+    pub key: String,
+    pub old: ModEntry,
+    pub new: ModEntry,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PackDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pack_version: Option<Change<Option<semver::Version>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forge: Option<Change<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_status: Option<Change<Option<ReleaseStatus>>>,
+    pub added: Vec<ModEntry>,
+    pub removed: Vec<ModEntry>,
+    pub updated: Vec<ModUpdate>,
+}
+
+fn change<T: PartialEq + Clone>(old: &T, new: &T) -> Option<Change<T>> {
+    if old == new {
+        None
+    } else {
+        Some(Change{ old: old.clone(), new: new.clone() })
+    }
+}
+
+fn by_key(mods: &[ModEntry]) -> BTreeMap<String, &ModEntry> {
+    mods.iter().map(|modd| (modd.key(), modd)).collect()
+}
+
+impl PackDiff {
+    pub fn between(old: &ModpackConfig, new: &ModpackConfig) -> Self {
+        let old_mods = by_key(&old.mods);
+        let new_mods = by_key(&new.mods);
+
+        let mut diff = Self{
+            pack_version: change(&old.pack_version, &new.pack_version),
+            forge: change(&old.forge, &new.forge),
+            release_status: change(&old.auto_update_release_status, &new.auto_update_release_status),
+            ..Self::default()
+        };
+
+        for (key, &new_mod) in &new_mods {
+            match old_mods.get(key) {
+                None => diff.added.push(new_mod.clone()),
+                Some(&old_mod) if old_mod != new_mod => diff.updated.push(ModUpdate{
+                    key: key.clone(),
+                    old: old_mod.clone(),
+                    new: new_mod.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (key, &old_mod) in &old_mods {
+            if !new_mods.contains_key(key) {
+                diff.removed.push(old_mod.clone());
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curseforge, mod_source::ModSource};
+
+    fn curse(id: &str, version: u64) -> ModEntry {
+        ModSource::CurseforgeMod(curseforge::Mod{ id: id.into(), version }).into()
+    }
+
+    #[test]
+    fn finds_added_removed_and_updated_mods() {
+        let old = ModpackConfig::for_test(vec![curse("jei", 1), curse("baubles", 5)]);
+        let new = ModpackConfig::for_test(vec![curse("jei", 2), curse("tinkers-construct", 7)]);
+        let diff = PackDiff::between(&old, &new);
+        assert_eq!(diff.added, vec![curse("tinkers-construct", 7)]);
+        assert_eq!(diff.removed, vec![curse("baubles", 5)]);
+        assert_eq!(diff.updated, vec![ModUpdate{ key: "jei".into(), old: curse("jei", 1), new: curse("jei", 2) }]);
+        assert_eq!(diff.forge, None);
+    }
+
+    #[test]
+    fn finds_forge_and_release_status_changes() {
+        let old = ModpackConfig::for_test(vec![]);
+        let mut new = ModpackConfig::for_test(vec![]);
+        assert!(PackDiff::between(&old, &new).is_empty());
+
+        new.forge = "1.12.2-14.23.5.2847".into();
+        new.auto_update_release_status = Some(ReleaseStatus::Beta);
+        let diff = PackDiff::between(&old, &new);
+        assert_eq!(diff.forge, Some(Change{ old: old.forge.clone(), new: new.forge.clone() }));
+        assert_eq!(diff.release_status, Some(Change{ old: None, new: Some(ReleaseStatus::Beta) }));
+    }
+}
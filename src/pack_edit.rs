@@ -0,0 +1,385 @@
+//! Editing packs from code, reporting what changed instead of printing it
+use crate::{
+    curseforge::{self, ReleaseStatus},
+    mod_source::{ModEntry, ModpackConfig, ModSource, Side, VersionConstraint},
+};
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("{} is already in the pack (as {})", key, existing))]
+    AlreadyInPack{
+        key: String,
+        existing: String,
+    },
+    #[snafu(display("no mod matching {} is in the pack", identifier))]
+    NotInPack{
+        identifier: String,
+    },
+    #[snafu(display("{} matches more than one mod: {}", identifier, matches.join(", ")))]
+    Ambiguous{
+        identifier: String,
+        matches: Vec<String>,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+///A top level setting of a pack
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum PackField {
+    Name(String),
+    Forge(String),
+    ///The minecraft version requirement
+    Version(semver::VersionReq),
+    AutoUpdateReleaseStatus(Option<ReleaseStatus>),
+    Icon(Option<String>),
+    PackVersion(Option<semver::Version>),
+}
+
+///A setting of a single mod entry
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum ModField {
+    Side(Side),
+    Pinned(bool),
+    ReleaseStatus(Option<ReleaseStatus>),
+    Constraint(Option<VersionConstraint>),
+    Optional(bool),
+    Description(Option<String>),
+    Group(Option<String>),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum PackChange {
+    Added(ModEntry),
+    Removed(ModEntry),
+    Replaced{
+        old: ModEntry,
+        new: ModEntry,
+    },
+    FieldChanged{
+        old: PackField,
+        new: PackField,
+    },
+    ModFieldChanged{
+        key: String,
+        old: ModField,
+        new: ModField,
+    },
+}
+
+impl std::fmt::Display for PackChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            PackChange::Added(ref modd) => write!(f, "Added {}", modd.describe()),
+            PackChange::Removed(ref modd) => write!(f, "Removed {}", modd.describe()),
+            PackChange::Replaced{ref old, ref new} => write!(f, "Replaced {} with {}", old.describe(), new.describe()),
+            PackChange::FieldChanged{ref old, ref new} => write!(f, "Changed {:?} to {:?}", old, new),
+            PackChange::ModFieldChanged{ref key, ref old, ref new} => write!(f, "Changed {} {:?} to {:?}", key, old, new),
+        }
+    }
+}
+
+impl ModSource {
+    ///Whether `identifier` refers to this mod, by key (`jei`, `group:artifact`), full identifier or curseforge url
+    pub fn matches(&self, identifier: &str) -> bool {
+        if self.key() == identifier || self.identifier_string() == identifier {
+            return true;
+        }
+        match curseforge::parse_modid_from_url(identifier) {
+            Ok(id) => self.key() == id,
+            Err(_) => false,
+        }
+    }
+}
+
+///Gives `kept` any settings only `dropped` has, so collapsing duplicate entries doesn't lose them
+fn carry_settings(kept: &mut ModEntry, dropped: &ModEntry) {
+    if kept.side.is_both() {
+        kept.side = dropped.side;
+    }
+    kept.pinned |= dropped.pinned;
+    kept.optional |= dropped.optional;
+    if kept.release_status.is_none() {
+        kept.release_status = dropped.release_status;
+    }
+    if kept.constraint.is_none() {
+        kept.constraint = dropped.constraint.clone();
+    }
+    if kept.description.is_none() {
+        kept.description = dropped.description.clone();
+    }
+    if kept.group.is_none() {
+        kept.group = dropped.group.clone();
+    }
+}
+
+impl ModpackConfig {
+    fn position(&self, identifier: &str) -> Result<usize> {
+        let matches: Vec<usize> = self.mods.iter()
+            .enumerate()
+            .filter(|(_, modd)| modd.matches(identifier))
+            .map(|(i, _)| i)
+            .collect();
+        match matches.len() {
+            0 => NotInPack{identifier}.fail(),
+            1 => Ok(matches[0]),
+            _ => Ambiguous{
+                identifier,
+                matches: matches.into_iter().map(|i| self.mods[i].identifier_string()).collect::<Vec<_>>(),
+            }.fail(),
+        }
+    }
+
+    pub fn find(&self, identifier: &str) -> Option<&ModEntry> {
+        self.position(identifier).ok().map(move |i| &self.mods[i])
+    }
+
+    pub fn find_mut(&mut self, identifier: &str) -> Option<&mut ModEntry> {
+        let i = self.position(identifier).ok()?;
+        Some(&mut self.mods[i])
+    }
+
+    ///Adds a mod that isn't in the pack yet
+    pub fn add_mod<M: Into<ModEntry>>(&mut self, entry: M) -> Result<PackChange> {
+        let entry = entry.into();
+        if let Some(existing) = self.mods.iter().find(|modd| modd.key() == entry.key()) {
+            return AlreadyInPack{
+                key: entry.key(),
+                existing: existing.describe(),
+            }.fail();
+        }
+        self.mods.push(entry.clone());
+        Ok(PackChange::Added(entry))
+    }
+
+    ///Adds a mod, replacing any other versions of it already in the pack while keeping their settings (e.g. side)
+    pub fn replace_mod(&mut self, modsource: ModSource) -> Vec<PackChange> {
+        let key = modsource.key();
+        let (old, kept): (Vec<_>, Vec<_>) = std::mem::replace(&mut self.mods, vec![])
+            .into_iter()
+            .partition(|modd| modd.key() == key);
+        self.mods = kept;
+
+        let new = match old.first() {
+            Some(existing) => ModEntry{
+                source: modsource,
+                ..existing.clone()
+            },
+            None => modsource.into(),
+        };
+        self.mods.push(new.clone());
+
+        if old.is_empty() {
+            vec![PackChange::Added(new)]
+        } else {
+            old.into_iter()
+                .filter(|old| *old != new)
+                .map(|old| PackChange::Replaced{ old, new: new.clone() })
+                .collect()
+        }
+    }
+
+    ///Sets a setting of the mod matching `identifier`, returning the change if it differed
+    pub fn set_mod_field(&mut self, identifier: &str, field: ModField) -> Result<Option<PackChange>> {
+        let i = self.position(identifier)?;
+        let entry = &mut self.mods[i];
+        let old = match field {
+            ModField::Side(side) => ModField::Side(std::mem::replace(&mut entry.side, side)),
+            ModField::Pinned(pinned) => ModField::Pinned(std::mem::replace(&mut entry.pinned, pinned)),
+            ModField::ReleaseStatus(status) => ModField::ReleaseStatus(std::mem::replace(&mut entry.release_status, status)),
+            ModField::Constraint(ref constraint) => ModField::Constraint(std::mem::replace(&mut entry.constraint, constraint.clone())),
+            ModField::Optional(optional) => ModField::Optional(std::mem::replace(&mut entry.optional, optional)),
+            ModField::Description(ref description) => ModField::Description(std::mem::replace(&mut entry.description, description.clone())),
+            ModField::Group(ref group) => ModField::Group(std::mem::replace(&mut entry.group, group.clone())),
+        };
+        Ok(if old == field {
+            None
+        } else {
+            Some(PackChange::ModFieldChanged{ key: entry.key(), old, new: field })
+        })
+    }
+
+    ///Points the mod matching `identifier` at `source`, e.g. after its project was renamed, keeping its settings
+    pub fn rename_mod(&mut self, identifier: &str, source: ModSource) -> Result<PackChange> {
+        let i = self.position(identifier)?;
+        let old = self.mods[i].clone();
+        self.mods[i].source = source;
+        Ok(PackChange::Replaced{ old, new: self.mods[i].clone() })
+    }
+
+    pub fn remove_mod(&mut self, identifier: &str) -> Result<PackChange> {
+        let i = self.position(identifier)?;
+        Ok(PackChange::Removed(self.mods.remove(i)))
+    }
+
+    pub fn add_mod_by_url(&mut self, mod_url: &str) -> crate::Result<Vec<PackChange>> {
+        let modsource: ModSource = curseforge::Mod::from_url(mod_url)?.into();
+        Ok(self.replace_mod(modsource))
+    }
+
+    ///Puts the pack in canonical order and collapses mods listed more than once to their highest version,
+    ///returning the entries that were dropped
+    pub fn normalize(&mut self) -> Vec<PackChange> {
+        //canonical order puts each mod's versions together, lowest first
+        self.canonicalize();
+        let mut removed = vec![];
+        let mut kept: Vec<ModEntry> = Vec::with_capacity(self.mods.len());
+        for entry in std::mem::replace(&mut self.mods, vec![]) {
+            if kept.last().map_or(false, |last| last.key() == entry.key()) {
+                let last = kept.pop().expect("just checked there is a last entry");
+                //a pinned version stays, even over a higher one
+                let (mut winner, loser) = if last.pinned && !entry.pinned { (last, entry) } else { (entry, last) };
+                carry_settings(&mut winner, &loser);
+                removed.push(PackChange::Removed(loser));
+                kept.push(winner);
+            } else {
+                kept.push(entry);
+            }
+        }
+        self.mods = kept;
+        removed
+    }
+
+    ///Sets a top level field, returning the change if it differed
+    pub fn set_field(&mut self, field: PackField) -> Option<PackChange> {
+        let old = match field {
+            PackField::Name(ref name) => PackField::Name(std::mem::replace(&mut self.name, name.clone())),
+            PackField::Forge(ref forge) => PackField::Forge(std::mem::replace(&mut self.forge, forge.clone())),
+            PackField::Version(ref version) => PackField::Version(std::mem::replace(&mut self.version, version.clone())),
+            PackField::AutoUpdateReleaseStatus(status) => PackField::AutoUpdateReleaseStatus(std::mem::replace(&mut self.auto_update_release_status, status)),
+            PackField::Icon(ref icon) => PackField::Icon(std::mem::replace(&mut self.icon, icon.clone())),
+            PackField::PackVersion(ref version) => PackField::PackVersion(std::mem::replace(&mut self.pack_version, version.clone())),
+        };
+        if old == field {
+            None
+        } else {
+            Some(PackChange::FieldChanged{ old, new: field })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::maven;
+
+    fn curse(id: &str, version: u64) -> ModSource {
+        ModSource::CurseforgeMod(curseforge::Mod{ id: id.into(), version })
+    }
+
+    fn entry(source: ModSource) -> ModEntry {
+        source.into()
+    }
+
+    fn maven_mod(coords: &str) -> ModSource {
+        ModSource::MavenMod{
+            repo: "https://maven.example.com/".into(),
+            artifact: coords.parse::<maven::Artifact>().unwrap(),
+            resolved_version: None,
+        }
+    }
+
+    fn pack(mods: Vec<ModSource>) -> ModpackConfig {
+        ModpackConfig::for_test(mods.into_iter().map(entry).collect())
+    }
+
+    #[test]
+    fn replaces_every_kind_of_mod() {
+        let mut pack = pack(vec![curse("jei", 1), maven_mod("com.example:library:1.0")]);
+        assert_eq!(pack.replace_mod(curse("jei", 2)), vec![PackChange::Replaced{ old: entry(curse("jei", 1)), new: entry(curse("jei", 2)) }]);
+        assert_eq!(
+            pack.replace_mod(maven_mod("com.example:library:1.1")),
+            vec![PackChange::Replaced{ old: entry(maven_mod("com.example:library:1.0")), new: entry(maven_mod("com.example:library:1.1")) }]
+        );
+        assert_eq!(pack.replace_mod(curse("baubles", 5)), vec![PackChange::Added(entry(curse("baubles", 5)))]);
+        assert_eq!(pack.replace_mod(curse("baubles", 5)), vec![]);
+        assert_eq!(pack.mods.len(), 3);
+    }
+
+    #[test]
+    fn finds_and_removes_by_identifier() {
+        let mut pack = pack(vec![curse("jei", 1), maven_mod("com.example:library:1.0")]);
+        assert_eq!(pack.find("com.example:library"), Some(&entry(maven_mod("com.example:library:1.0"))));
+        assert_eq!(pack.find("https://www.curseforge.com/minecraft/mc-mods/jei"), Some(&entry(curse("jei", 1))));
+        assert_eq!(pack.remove_mod("jei").unwrap(), PackChange::Removed(entry(curse("jei", 1))));
+        assert!(pack.remove_mod("jei").is_err());
+        assert!(pack.add_mod(maven_mod("com.example:library:2.0")).is_err());
+    }
+
+    #[test]
+    fn reports_field_changes() {
+        let mut pack = pack(vec![]);
+        assert_eq!(pack.set_field(PackField::Name("Test Pack".into())), None);
+        assert_eq!(
+            pack.set_field(PackField::Name("Renamed".into())),
+            Some(PackChange::FieldChanged{ old: PackField::Name("Test Pack".into()), new: PackField::Name("Renamed".into()) })
+        );
+        assert_eq!(pack.name, "Renamed");
+    }
+
+    #[test]
+    fn keeps_mod_settings_when_replacing() {
+        let mut pack = pack(vec![curse("jei", 1)]);
+        assert!(pack.set_mod_field("jei", ModField::Side(Side::Client)).unwrap().is_some());
+        pack.replace_mod(curse("jei", 2));
+        assert_eq!(pack.mods, vec![ModEntry{ side: Side::Client, ..entry(curse("jei", 2)) }]);
+    }
+
+    #[test]
+    fn normalizes_to_the_highest_version() {
+        let mut pack = pack(vec![
+            curse("jei", 3),
+            maven_mod("com.example:library:1.10"),
+            curse("jei", 12),
+            curse("baubles", 5),
+            maven_mod("com.example:library:1.9"),
+            curse("jei", 3),
+        ]);
+        assert_eq!(pack.normalize(), vec![
+            PackChange::Removed(entry(maven_mod("com.example:library:1.9"))),
+            PackChange::Removed(entry(curse("jei", 3))),
+        ]);
+        assert_eq!(pack.mods, vec![
+            entry(curse("baubles", 5)),
+            entry(maven_mod("com.example:library:1.10")),
+            entry(curse("jei", 12)),
+        ]);
+        assert_eq!(pack.normalize(), vec![]);
+    }
+
+    #[test]
+    fn normalizing_keeps_settings_of_dropped_entries() {
+        let mut pack = pack(vec![curse("jei", 3), curse("jei", 12), curse("optifine", 1), curse("optifine", 2)]);
+        pack.mods[0].pinned = true;
+        pack.mods[2].side = Side::Client;
+        pack.mods[2].optional = true;
+        pack.normalize();
+        assert_eq!(pack.mods, vec![
+            ModEntry{ pinned: true, ..entry(curse("jei", 3)) },
+            ModEntry{ side: Side::Client, optional: true, ..entry(curse("optifine", 2)) },
+        ]);
+    }
+
+    #[test]
+    fn round_trips_upgrade_settings() {
+        let mut pack = pack(vec![curse("jei", 1)]);
+        pack.set_mod_field("jei", ModField::Pinned(true)).unwrap();
+        pack.set_mod_field("jei", ModField::ReleaseStatus(Some(ReleaseStatus::Alpha))).unwrap();
+        pack.set_mod_field("jei", ModField::Constraint(Some("[1,100)".parse().unwrap()))).unwrap();
+
+        let json = serde_json::to_value(&pack.mods[0]).unwrap();
+        assert_eq!(json["pinned"], json!(true));
+        assert_eq!(json["constraint"], json!("[1,100)"));
+        let entry: ModEntry = serde_json::from_value(json).unwrap();
+        assert_eq!(entry, pack.mods[0]);
+        assert!(entry.allows_version("99"));
+        assert!(!entry.allows_version("100"));
+        assert_eq!(entry.upgrade_release_status(ReleaseStatus::Release), ReleaseStatus::Alpha);
+
+        //unset settings stay out of the file
+        let plain = serde_json::to_value(&ModEntry::from(curse("jei", 1))).unwrap();
+        assert_eq!(plain.as_object().unwrap().len(), 1);
+    }
+}
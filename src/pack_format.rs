@@ -0,0 +1,138 @@
+//! The file formats a pack can be written in, chosen by file extension
+use serde::{de::DeserializeOwned, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::path::Path;
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("{}", source))]
+    Json{
+        source: crate::async_json::Error,
+    },
+    #[snafu(display("Toml error: {}", source))]
+    Toml{
+        source: serde_path_to_error::Error<toml::de::Error>,
+    },
+    #[snafu(display("Toml error: {}", source))]
+    TomlSerialize{
+        source: toml::ser::Error,
+    },
+    #[snafu(display("Yaml error: {}", source))]
+    Yaml{
+        source: serde_yaml::Error,
+    },
+    #[snafu(display("`{}` only belongs in a pack which extends another, and those list no `mods` of their own", key))]
+    OverlayKeyInRealPack{
+        key: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackFormat{
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl PackFormat{
+    pub const ALL: &'static [PackFormat] = &[PackFormat::Json, PackFormat::Toml, PackFormat::Yaml];
+
+    ///Picks the format from the file extension, falling back to json
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase).as_ref().map(String::as_str) {
+            Some("toml") => PackFormat::Toml,
+            Some("yaml") | Some("yml") => PackFormat::Yaml,
+            _ => PackFormat::Json,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PackFormat::Json => "json",
+            PackFormat::Toml => "toml",
+            PackFormat::Yaml => "yaml",
+        }
+    }
+
+    ///The name the pack is stored under inside a packaged one-click installer
+    pub fn hybrid_entry(self) -> &'static str {
+        match self {
+            PackFormat::Json => "config.json",
+            PackFormat::Toml => "config.toml",
+            PackFormat::Yaml => "config.yaml",
+        }
+    }
+
+    ///Deserializes `s`, with errors saying which field they happened in as well as where
+    pub fn parse<T: DeserializeOwned>(self, s: &str) -> Result<T, Error> {
+        match self {
+            PackFormat::Json => crate::async_json::from_str(s).context(Json),
+            PackFormat::Toml => serde_path_to_error::deserialize(&mut toml::Deserializer::new(s)).context(Toml),
+            //yaml errors already say which field they happened in
+            PackFormat::Yaml => serde_yaml::from_str(s).context(Yaml),
+        }
+    }
+
+    pub fn to_string<T: Serialize>(self, t: &T) -> Result<String, Error> {
+        match self {
+            PackFormat::Json => crate::async_json::to_string_pretty(t).context(Json),
+            PackFormat::Toml => {
+                //toml needs plain values written before any tables, which going through a toml::Value sorts out,
+                //keeping the fields in order otherwise
+                let value = toml::Value::try_from(t).context(TomlSerialize)?;
+                toml::to_string_pretty(&value).context(TomlSerialize)
+            }
+            PackFormat::Yaml => serde_yaml::to_string(t).context(Yaml),
+        }
+    }
+
+    ///Puts the comment block from the top of `previous` (the file being replaced) back on `contents`.
+    ///This is the only comment kept: ones between or after fields are lost when the pack is saved, and json has none at all.
+    pub fn with_header(self, previous: Option<&str>, contents: String) -> String {
+        if self == PackFormat::Json {
+            return contents;
+        }
+        let header: String = previous.unwrap_or("")
+            .lines()
+            .take_while(|line| line.trim_start().starts_with('#'))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        header + &contents
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mod_source::{ModpackConfig, ModSource};
+
+    fn pack() -> ModpackConfig {
+        let jei = ModSource::CurseforgeMod(crate::curseforge::Mod{ id: "jei".into(), version: 1 });
+        ModpackConfig{ icon: Some("https://example.com/icon.png".into()), ..ModpackConfig::for_test(vec![jei.into()]) }
+    }
+
+    #[test]
+    fn picks_format_by_extension() {
+        assert_eq!(PackFormat::for_path(Path::new("pack.toml")), PackFormat::Toml);
+        assert_eq!(PackFormat::for_path(Path::new("pack.YML")), PackFormat::Yaml);
+        assert_eq!(PackFormat::for_path(Path::new("pack.json")), PackFormat::Json);
+        assert_eq!(PackFormat::for_path(Path::new("pack")), PackFormat::Json);
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        let pack = pack();
+        for &format in PackFormat::ALL {
+            let written = format.to_string(&pack).unwrap();
+            let read: ModpackConfig = format.parse(&written).unwrap();
+            assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&pack).unwrap(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn keeps_the_header_comment() {
+        let previous = "# The lite pack\n# edit with care\nname = \"old\"\n# not kept\n";
+        assert_eq!(PackFormat::Toml.with_header(Some(previous), "name = \"new\"\n".into()), "# The lite pack\n# edit with care\nname = \"new\"\n");
+        assert_eq!(PackFormat::Json.with_header(Some(previous), "{}".into()), "{}");
+    }
+}
@@ -0,0 +1,185 @@
+//! Three-way merging of pack files, understanding mod entries rather than lines of json
+use crate::mod_source::{ModEntry, ModpackConfig};
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+};
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    ///Both sides changed the same mod in incompatible ways, e.g. one removed it while the other updated it
+    Mod {
+        key: String,
+        base: Option<ModEntry>,
+        ours: Option<ModEntry>,
+        theirs: Option<ModEntry>,
+    },
+    ///Both sides changed a pack field to different values
+    Field {
+        field: String,
+        base: Value,
+        ours: Value,
+        theirs: Value,
+    },
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let describe = |modd: &Option<ModEntry>| modd.as_ref().map(|modd| modd.describe()).unwrap_or_else(|| "removed".to_owned());
+        match *self {
+            Conflict::Mod{ref key, ref base, ref ours, ref theirs} => write!(
+                f, "{}: base {}, ours {}, theirs {}", key, describe(base), describe(ours), describe(theirs)
+            ),
+            Conflict::Field{ref field, ref base, ref ours, ref theirs} => write!(
+                f, "{}: base {}, ours {}, theirs {}", field, base, ours, theirs
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    ///The merged pack, taking our side of any conflicts
+    pub merged: ModpackConfig,
+    pub conflicts: Vec<Conflict>,
+}
+
+///The usual three-way rule: take whichever side changed, or None if both changed differently
+fn merge_value<'a, T: PartialEq>(base: &'a T, ours: &'a T, theirs: &'a T) -> Option<&'a T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn merge_mod(key: &str, base: Option<&ModEntry>, ours: Option<&ModEntry>, theirs: Option<&ModEntry>) -> (Option<ModEntry>, Option<Conflict>) {
+    if let Some(merged) = merge_value(&base, &ours, &theirs) {
+        return (merged.cloned(), None);
+    }
+    if let (Some(ours), Some(theirs)) = (ours, theirs) {
+        //both sides bumped the version, so take the newer one
+        match ours.compare_versions(theirs).filter(|_| ours.side == theirs.side) {
+            Some(Ordering::Less) => return (Some(theirs.clone()), None),
+            Some(Ordering::Greater) => return (Some(ours.clone()), None),
+            _ => {}
+        }
+    }
+    (ours.cloned(), Some(Conflict::Mod{
+        key: key.to_owned(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    }))
+}
+
+fn by_key(mods: &[ModEntry]) -> BTreeMap<String, &ModEntry> {
+    mods.iter().map(|modd| (modd.key(), modd)).collect()
+}
+
+fn fields(pack: &ModpackConfig) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(pack).expect("packs are always serializable") {
+        Value::Object(mut fields) => {
+            fields.remove("mods");
+            fields
+        }
+        _ => unreachable!("packs serialize to objects"),
+    }
+}
+
+pub fn merge(base: &ModpackConfig, ours: &ModpackConfig, theirs: &ModpackConfig) -> MergeResult {
+    let mut conflicts = vec![];
+
+    let (base_fields, our_fields, their_fields) = (fields(base), fields(ours), fields(theirs));
+    let names: BTreeSet<&String> = base_fields.keys().chain(our_fields.keys()).chain(their_fields.keys()).collect();
+    let mut merged_fields = serde_json::Map::new();
+    for name in names {
+        let get = |fields: &serde_json::Map<String, Value>| fields.get(name).cloned().unwrap_or(Value::Null);
+        let (base, ours, theirs) = (get(&base_fields), get(&our_fields), get(&their_fields));
+        let merged = match merge_value(&base, &ours, &theirs) {
+            Some(merged) => merged.clone(),
+            None => {
+                conflicts.push(Conflict::Field{
+                    field: name.clone(),
+                    base: base.clone(),
+                    ours: ours.clone(),
+                    theirs: theirs.clone(),
+                });
+                ours.clone()
+            }
+        };
+        if !merged.is_null() {
+            merged_fields.insert(name.clone(), merged);
+        }
+    }
+
+    let (base_mods, our_mods, their_mods) = (by_key(&base.mods), by_key(&ours.mods), by_key(&theirs.mods));
+    let keys: BTreeSet<&String> = base_mods.keys().chain(our_mods.keys()).chain(their_mods.keys()).collect();
+    let mut mods = vec![];
+    for key in keys {
+        let (merged, conflict) = merge_mod(key, base_mods.get(key).cloned(), our_mods.get(key).cloned(), their_mods.get(key).cloned());
+        mods.extend(merged);
+        conflicts.extend(conflict);
+    }
+
+    merged_fields.insert("mods".into(), serde_json::to_value(&mods).expect("mods are always serializable"));
+    let mut merged: ModpackConfig = serde_json::from_value(Value::Object(merged_fields)).expect("merging valid packs field by field gives a valid pack");
+    merged.canonicalize();
+
+    MergeResult{ merged, conflicts }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curseforge, mod_source::ModSource};
+
+    fn curse(id: &str, version: u64) -> ModEntry {
+        ModSource::CurseforgeMod(curseforge::Mod{ id: id.into(), version }).into()
+    }
+
+    fn pack(name: &str, mods: Vec<ModEntry>) -> ModpackConfig {
+        ModpackConfig{ name: name.into(), ..ModpackConfig::for_test(mods) }
+    }
+
+    #[test]
+    fn merges_independent_changes() {
+        let base = pack("Pack", vec![curse("jei", 1), curse("baubles", 5)]);
+        let ours = pack("Pack", vec![curse("jei", 1), curse("baubles", 5), curse("mekanism", 3)]);
+        let theirs = pack("Renamed Pack", vec![curse("jei", 2)]);
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(result.conflicts, vec![]);
+        assert_eq!(result.merged.name, "Renamed Pack");
+        assert_eq!(result.merged.mods, vec![curse("jei", 2), curse("mekanism", 3)]);
+    }
+
+    #[test]
+    fn takes_the_higher_version_when_both_sides_update() {
+        let base = pack("Pack", vec![curse("jei", 1)]);
+        let ours = pack("Pack", vec![curse("jei", 3)]);
+        let theirs = pack("Pack", vec![curse("jei", 2)]);
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(result.conflicts, vec![]);
+        assert_eq!(result.merged.mods, vec![curse("jei", 3)]);
+    }
+
+    #[test]
+    fn flags_real_conflicts() {
+        let base = pack("Pack", vec![curse("jei", 1)]);
+        let ours = pack("Our Pack", vec![]);
+        let theirs = pack("Their Pack", vec![curse("jei", 2)]);
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(result.conflicts.len(), 2);
+        assert!(result.conflicts.contains(&Conflict::Mod{
+            key: "jei".into(),
+            base: Some(curse("jei", 1)),
+            ours: None,
+            theirs: Some(curse("jei", 2)),
+        }));
+        assert_eq!(result.merged.name, "Our Pack");
+        assert_eq!(result.merged.mods, vec![]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Packs made from another pack with a few changes, so variants of a pack can share most of it
+use crate::{
+    curseforge::ReleaseStatus,
+    maven::RepositoryConfig,
+    mod_source::{ModList, ModpackConfig},
+    pack_edit::{self, PackField},
+};
+use schemars::JsonSchema;
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error{
+    #[snafu(display("can't remove {} from the base pack: {}", identifier, source))]
+    Removing{
+        identifier: String,
+        source: pack_edit::Error,
+    },
+}
+
+///Top level settings to change from the base pack
+#[derive(Serialize, Deserialize, Debug, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PackOverrides{
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub version: Option<semver::VersionReq>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_update_release_status: Option<ReleaseStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub pack_version: Option<semver::Version>,
+    ///Replaces the base pack's repositories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<RepositoryConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct PackOverlay{
+    ///The pack this one is based on, as a path relative to this pack or a url
+    pub extends: String,
+    ///Mods to add, replacing the base pack's entry for any it already has
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add: ModList,
+    ///Mods to take out of the base pack, as curseforge ids, maven group:artifact, or urls
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+    #[serde(default, rename = "override")]
+    pub overrides: PackOverrides,
+}
+
+impl PackOverlay{
+    ///The base pack with this overlay's changes made to it
+    pub fn apply(self, mut base: ModpackConfig) -> Result<ModpackConfig, Error> {
+        for identifier in self.remove {
+            base.remove_mod(&identifier).context(Removing{identifier})?;
+        }
+        for entry in self.add {
+            base.mods.retain(|existing| existing.key() != entry.key());
+            base.mods.push(entry);
+        }
+
+        let PackOverrides{name, version, forge, auto_update_release_status, icon, pack_version, repositories} = self.overrides;
+        let fields = vec![
+            name.map(PackField::Name),
+            version.map(PackField::Version),
+            forge.map(PackField::Forge),
+            auto_update_release_status.map(|status| PackField::AutoUpdateReleaseStatus(Some(status))),
+            icon.map(|icon| PackField::Icon(Some(icon))),
+            pack_version.map(|version| PackField::PackVersion(Some(version))),
+        ];
+        for field in fields.into_iter().flatten() {
+            base.set_field(field);
+        }
+        if let Some(repositories) = repositories {
+            base.repositories = repositories;
+        }
+        Ok(base)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mod_source::IndirectableModpack;
+
+    fn base() -> ModpackConfig {
+        let mods = serde_json::from_value(json!([
+            {"CurseforgeMod": {"id": "jei", "version": 1}},
+            {"CurseforgeMod": {"id": "optifine", "version": 3}},
+            {"CurseforgeMod": {"id": "journeymap", "version": 7}},
+        ])).unwrap();
+        ModpackConfig{ name: "Full Pack".into(), ..ModpackConfig::for_test(mods) }
+    }
+
+    fn overlay(json: serde_json::Value) -> PackOverlay {
+        match IndirectableModpack::parse(crate::pack_format::PackFormat::Json, &json.to_string()).unwrap() {
+            IndirectableModpack::Overlay(overlay) => overlay,
+            other => panic!("parsed as {:?}", other),
+        }
+    }
+
+    #[test]
+    fn adds_removes_and_overrides() {
+        let lite = overlay(json!({
+            "extends": "full.json",
+            "add": [{"CurseforgeMod": {"id": "jei", "version": 2}, "side": "client"}],
+            "remove": ["optifine"],
+            "override": {"name": "Lite Pack"},
+        }));
+        let pack = lite.apply(base()).unwrap();
+        assert_eq!(pack.name, "Lite Pack");
+        assert_eq!(pack.forge, "1.12.2-14.23.5.2838");
+        let mods: Vec<_> = pack.mods.iter().map(|entry| entry.describe()).collect();
+        assert_eq!(mods, vec!["curseforge mod journeymap (file 7)".to_owned(), "curseforge mod jei (file 2)".to_owned()]);
+        assert!(!pack.mods[1].side.is_both());
+    }
+
+    #[test]
+    fn overlays_are_validated_as_the_pack_they_make() {
+        let newer = overlay(json!({
+            "extends": "full.json",
+            "override": {"version": "~1.14.4"},
+        }));
+        let fields: Vec<_> = crate::pack_validate::check(&newer.apply(base()).unwrap()).into_iter().map(|problem| problem.field).collect();
+        assert_eq!(fields, vec!["forge".to_owned()]);
+    }
+
+    #[test]
+    fn rejects_real_packs_with_overlay_keys() {
+        let mut mixed = serde_json::to_value(ModpackConfig::for_test(vec![])).unwrap();
+        mixed["extends"] = json!("full.json");
+        assert!(IndirectableModpack::parse(crate::pack_format::PackFormat::Json, &mixed.to_string()).is_err());
+    }
+
+    #[test]
+    fn fails_to_remove_mods_the_base_lacks() {
+        let server = overlay(json!({
+            "extends": "https://example.com/full.json",
+            "remove": ["minimap"],
+        }));
+        assert!(server.apply(base()).is_err());
+    }
+}
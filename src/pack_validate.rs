@@ -0,0 +1,143 @@
+//! Finding mistakes in pack files that still parse
+use crate::{
+    download::{self, HttpSimple},
+    forge_version,
+    mod_source::ModpackConfig,
+};
+use http::Uri;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+};
+
+///Something wrong with a pack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem{
+    ///Where in the pack the problem is, such as `forge` or `mods[3]`
+    pub field: String,
+    pub message: String,
+}
+
+impl Problem{
+    fn new(field: impl Into<String>, message: String) -> Self {
+        Self{
+            field: field.into(),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+///A JSON Schema for pack files, for editors to check and complete them with
+pub fn schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(ModpackConfig)
+}
+
+///The minecraft version a forge version is for, from forge's `<minecraft>-<forge>` versions
+fn forge_minecraft_version(forge: &str) -> Option<semver::Version> {
+    let minecraft = forge.split('-').next()?;
+    semver::Version::parse(minecraft)
+        .or_else(|_| semver::Version::parse(&format!("{}.0", minecraft)))
+        .ok()
+}
+
+///Problems which can be found from the pack alone.
+///This must be given the pack as written, since loading a pack normalizes duplicate mods away.
+pub fn check(pack: &ModpackConfig) -> Vec<Problem> {
+    let mut problems = vec![];
+    if pack.name.trim().is_empty() {
+        problems.push(Problem::new("name", "the pack has no name".into()));
+    }
+    match forge_minecraft_version(&pack.forge) {
+        Some(ref minecraft) if !pack.version.matches(minecraft) => problems.push(Problem::new("forge", format!(
+            "forge {} is for minecraft {}, which the pack's version {} doesn't include", pack.forge, minecraft, pack.version
+        ))),
+        Some(_) => {}
+        None => problems.push(Problem::new("forge", format!(
+            "forge {} doesn't start with the minecraft version it's for, as in 1.12.2-14.23.5.2838", pack.forge
+        ))),
+    }
+    let mut first_listed: HashMap<String, usize> = HashMap::new();
+    for (i, entry) in pack.mods.iter().enumerate() {
+        match first_listed.get(&entry.key()) {
+            Some(first) => problems.push(Problem::new(format!("mods[{}]", i), format!("{} is already listed as mods[{}]", entry.key(), first))),
+            None => {
+                first_listed.insert(entry.key(), i);
+            }
+        }
+    }
+    problems
+}
+
+///Problems which need the network to find: a forge version which doesn't exist, or an icon which can't be fetched
+pub async fn check_online(pack: ModpackConfig, http_client: HttpSimple, manager: download::Manager) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    let forge = pack.forge_maven_artifact().artifact;
+    let forge_repo = Uri::from_str(forge_version::BASE_URL).expect("const Uri should always be valid");
+    let repos = pack.repository_config().repos_for(&forge.group, Some(&forge_repo));
+    match forge.fetch_metadata(None, repos, manager).await {
+        Ok(ref metadata) if metadata.versioning.versions.contains(&pack.forge) => {}
+        Ok(_) => problems.push(Problem::new("forge", format!("forge {} doesn't exist", pack.forge))),
+        Err(e) => problems.push(Problem::new("forge", format!("couldn't check forge {} exists: {}", pack.forge, e))),
+    }
+
+    //other icons are data urls or launcher built ins
+    if let Some(icon) = pack.icon.filter(|icon| icon.starts_with("http://") || icon.starts_with("https://")) {
+        let fetched = match Uri::from_str(&icon) {
+            Ok(uri) => match http_client.get_following_redirects(uri) {
+                Ok(follower) => follower.await.map(|(res, _url)| res.status()).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        match fetched {
+            Ok(status) if status.is_success() => {}
+            Ok(status) => problems.push(Problem::new("icon", format!("icon {} can't be fetched: {}", icon, status))),
+            Err(e) => problems.push(Problem::new("icon", format!("icon {} can't be fetched: {}", icon, e))),
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pack(version: &str, forge: &str, mods: serde_json::Value) -> ModpackConfig {
+        ModpackConfig{
+            version: semver::VersionReq::parse(version).unwrap(),
+            forge: forge.into(),
+            ..ModpackConfig::for_test(serde_json::from_value(mods).unwrap())
+        }
+    }
+
+    #[test]
+    fn finds_duplicates_and_mismatched_forge() {
+        let jei = json!({"CurseforgeMod": {"id": "jei", "version": 1}});
+        let jei_again = json!({"CurseforgeMod": {"id": "jei", "version": 2}});
+        let pack = pack("~1.14.4", "1.12.2-14.23.5.2838", json!([jei, jei_again]));
+        let fields: Vec<_> = check(&pack).into_iter().map(|problem| problem.field).collect();
+        assert_eq!(fields, vec!["forge".to_owned(), "mods[1]".to_owned()]);
+    }
+
+    #[test]
+    fn accepts_forge_for_the_pack_version() {
+        assert_eq!(check(&pack("~1.12.2", "1.12.2-14.23.5.2838", json!([]))), vec![]);
+        assert_eq!(check(&pack("~1.14", "1.14-26.0.63", json!([]))), vec![]);
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        let json = "{\n\"version\": \"not a version\",\n\"name\": \"Test Pack\"\n}";
+        let message = crate::async_json::from_str::<ModpackConfig>(json).unwrap_err().to_string();
+        assert!(message.contains("version: "), "{}", message);
+        assert!(message.contains("line 2"), "{}", message);
+    }
+}
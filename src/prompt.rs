@@ -0,0 +1,104 @@
+//! Asking the user questions on the console
+use std::io::Write;
+
+macro_rules! print_inline{
+    ($($args:tt)+) => {{
+        print!($($args)+);
+        if let Err(e) = std::io::stdout().flush(){
+            panic!("Failed to flush stdout: {}",e);
+        }
+    }};
+}
+
+macro_rules! readln{
+    () => {{
+        let mut new_name = String::new();
+        if let Err(e) = std::io::stdin().read_line(&mut new_name){
+            panic!("Failed to read stdin: {}",e);
+        }
+        new_name
+    }};
+}
+
+#[derive(Debug,PartialEq, Eq)]
+pub enum Response {
+    Yes,
+    No,
+}
+
+impl Response{
+    pub fn from_str(s: &str) -> Result<Option<Self>, ()> {
+        use nom::bytes::complete::*;
+        use nom::combinator::*;
+        use nom::branch::*;
+
+        fn error<'a, I>() -> impl (Fn(nom::Err<(I,nom::error::ErrorKind)>) -> ()) + 'a{
+            move |_|{}
+        }
+
+        let (rest,out) = alt((
+            map(tag_no_case("yes"), |_| Some(Self::Yes)),
+            map(tag_no_case("y"), |_| Some(Self::Yes)),
+            map(tag_no_case("no"), |_| Some(Self::No)),
+            map(tag_no_case("n"), |_| Some(Self::No)),
+            map(tag(""), |_| None)
+        ))(s.trim()).map_err(error())?;
+        if rest.is_empty(){
+            Ok(out)
+        } else {
+            Err(())
+        }
+    }
+}
+
+pub fn prompt_yes_no(prompt: &str, default: Response) -> Response {
+    loop{
+        match default {
+            Response::Yes => print_inline!("{}[Y/n]",prompt),
+            Response::No => print_inline!("{}[y/N]",prompt),
+        }
+        let line = readln!();
+        match Response::from_str(line.as_str()){
+            Ok(Some(r)) => return r,
+            Ok(None) => return default,
+            Err(_) => println!("Please enter yes, no, or nothing."),
+        }
+    }
+}
+
+///Whether there's someone at the console to answer, rather than input being piped in or closed
+pub fn is_interactive() -> bool {
+    atty::is(atty::Stream::Stdin)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::Response;
+
+    #[test]
+    fn response_parses_yes(){
+        assert_eq!(Response::from_str("yes"),Ok(Some(Response::Yes)));
+        assert_eq!(Response::from_str("Yes"),Ok(Some(Response::Yes)));
+        assert_eq!(Response::from_str("y"),Ok(Some(Response::Yes)));
+        assert_eq!(Response::from_str("Y"),Ok(Some(Response::Yes)));
+    }
+
+    #[test]
+    fn response_parses_no(){
+        assert_eq!(Response::from_str("no"),Ok(Some(Response::No)));
+        assert_eq!(Response::from_str("No"),Ok(Some(Response::No)));
+        assert_eq!(Response::from_str("n"),Ok(Some(Response::No)));
+        assert_eq!(Response::from_str("N"),Ok(Some(Response::No)));
+    }
+
+    #[test]
+    fn response_parses_other(){
+        assert!(Response::from_str("adsafggg").is_err());
+        assert!(Response::from_str("??£££23").is_err());
+    }
+
+    #[test]
+    fn response_parses_empty(){
+        assert_eq!(Response::from_str(""),Ok(None));
+    }
+}
@@ -0,0 +1,244 @@
+//! What an upgrade found for each mod, and rendering that for people to read
+use crate::{
+    curseforge::{ReleaseStatus, api::SearchResult},
+    mod_source::{ModEntry, ModpackConfig},
+};
+use std::path::Path;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome{
+    Updated,
+    ///An update was available, but was turned down at the prompt
+    Declined,
+    ///An update was available, but the mod is pinned
+    Pinned,
+    Skipped,
+    UpToDate,
+    NotFound,
+    Compatible(ReleaseStatus),
+    Incompatible,
+    ///We have no way to check this mod, so it must be checked by hand
+    Unchecked,
+    ///The curseforge project no longer exists
+    Deleted,
+    ///The curseforge project was archived by its authors
+    Archived,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Outcome::Updated => f.write_str("updated"),
+            Outcome::Declined => f.write_str("update declined"),
+            Outcome::Pinned => f.write_str("pinned"),
+            Outcome::Skipped => f.write_str("skipped"),
+            Outcome::UpToDate => f.write_str("up to date"),
+            Outcome::NotFound => f.write_str("no matching files"),
+            Outcome::Compatible(ReleaseStatus::Release) => f.write_str("compatible"),
+            Outcome::Compatible(status) => write!(f, "compatible ({})", status.value().to_lowercase()),
+            Outcome::Incompatible => f.write_str("incompatible"),
+            Outcome::Unchecked => f.write_str("check by hand"),
+            Outcome::Deleted => f.write_str("project deleted"),
+            Outcome::Archived => f.write_str("project archived"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ModReport{
+    pub key: String,
+    pub current: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<String>,
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_url: Option<String>,
+    ///Other mods that could stand in for an incompatible one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<SearchResult>,
+}
+
+impl ModReport{
+    pub fn new(entry: &ModEntry, available: Option<String>, outcome: Outcome) -> Self {
+        Self{
+            key: entry.key(),
+            current: entry.version_string(),
+            available,
+            outcome,
+            project_url: entry.guess_project_url(),
+            alternatives: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct UpgradeReport{
+    pub pack_name: String,
+    pub minecraft_version: semver::VersionReq,
+    pub dry_run: bool,
+    ///Whether the pack file was rewritten
+    pub applied: bool,
+    pub pack_version: Option<semver::Version>,
+    pub mods: Vec<ModReport>,
+}
+
+impl UpgradeReport{
+    pub fn new(pack: &ModpackConfig, minecraft_version: semver::VersionReq, dry_run: bool, applied: bool, mut mods: Vec<ModReport>) -> Self {
+        mods.sort_by(|a, b| a.key.cmp(&b.key));
+        Self{
+            pack_name: pack.name.clone(),
+            minecraft_version,
+            dry_run,
+            applied,
+            pack_version: pack.pack_version.clone(),
+            mods,
+        }
+    }
+
+    fn ready(&self) -> usize {
+        self.mods.iter().filter(|modd| match modd.outcome {
+            Outcome::Incompatible | Outcome::NotFound | Outcome::Unchecked | Outcome::Deleted | Outcome::Archived => false,
+            _ => true,
+        }).count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat{
+    Json,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat{
+    ///Picks the format from the file extension, falling back to json
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase).as_ref().map(String::as_str) {
+            Some("md") | Some("markdown") => ReportFormat::Markdown,
+            Some("html") | Some("htm") => ReportFormat::Html,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn alternative_url(alternative: &SearchResult) -> String {
+    format!("https://www.curseforge.com/minecraft/mc-mods/{}", alternative.id)
+}
+
+fn render_markdown(report: &UpgradeReport) -> String {
+    let mut out = format!("# {} on minecraft {}\n\n", escape_markdown(&report.pack_name), report.minecraft_version);
+    out += &format!("{} of {} mods are ready.\n\n", report.ready(), report.mods.len());
+    out += "| Mod | Current | Available | Status | Project |\n";
+    out += "| --- | --- | --- | --- | --- |\n";
+    for modd in &report.mods {
+        out += &format!("| {} | {} | {} | {} | {} |\n",
+            escape_markdown(&modd.key),
+            escape_markdown(&modd.current),
+            modd.available.as_ref().map(|available| escape_markdown(available)).unwrap_or_default(),
+            modd.outcome,
+            modd.project_url.as_ref().map(|url| format!("<{}>", url)).unwrap_or_default());
+    }
+    let with_alternatives: Vec<_> = report.mods.iter().filter(|modd| !modd.alternatives.is_empty()).collect();
+    if !with_alternatives.is_empty() {
+        out += "\n## Alternatives\n";
+        for modd in with_alternatives {
+            out += &format!("\n### {}\n\n", escape_markdown(&modd.key));
+            for alternative in &modd.alternatives {
+                out += &format!("- [{}]({}): {}\n", escape_markdown(&alternative.name), alternative_url(alternative), escape_markdown(&alternative.summary));
+            }
+        }
+    }
+    out
+}
+
+fn render_html(report: &UpgradeReport) -> String {
+    let link = |url: &str, text: &str| format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text));
+    let title = format!("{} on minecraft {}", report.pack_name, report.minecraft_version);
+    let mut out = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n", escape_html(&title));
+    out += &format!("<p>{} of {} mods are ready.</p>\n", report.ready(), report.mods.len());
+    out += "<table>\n<tr><th>Mod</th><th>Current</th><th>Available</th><th>Status</th><th>Project</th><th>Alternatives</th></tr>\n";
+    for modd in &report.mods {
+        let alternatives: Vec<String> = modd.alternatives.iter()
+            .map(|alternative| link(&alternative_url(alternative), &alternative.name))
+            .collect();
+        out += &format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&modd.key),
+            escape_html(&modd.current),
+            modd.available.as_ref().map(|available| escape_html(available)).unwrap_or_default(),
+            escape_html(&modd.outcome.to_string()),
+            modd.project_url.as_ref().map(|url| link(url, url)).unwrap_or_default(),
+            alternatives.join(", "));
+    }
+    out += "</table>\n</body>\n</html>\n";
+    out
+}
+
+pub fn render(report: &UpgradeReport, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Json => serde_json::to_string_pretty(report).expect("reports are always serializable"),
+        ReportFormat::Markdown => render_markdown(report),
+        ReportFormat::Html => render_html(report),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curseforge, mod_source::ModSource};
+
+    fn report() -> UpgradeReport {
+        let jei: ModEntry = ModSource::CurseforgeMod(curseforge::Mod{ id: "jei".into(), version: 1 }).into();
+        let baubles: ModEntry = ModSource::CurseforgeMod(curseforge::Mod{ id: "baubles".into(), version: 5 }).into();
+        let mut incompatible = ModReport::new(&baubles, None, Outcome::Incompatible);
+        incompatible.alternatives.push(SearchResult{
+            id: "bauble-box".into(),
+            name: "Bauble <Box>".into(),
+            summary: "Holds | things".into(),
+            downloads: None,
+            game_versions: vec!["1.14.4".into()],
+        });
+        UpgradeReport{
+            pack_name: "Test Pack".into(),
+            minecraft_version: semver::VersionReq::parse("~1.14.4").unwrap(),
+            dry_run: true,
+            applied: false,
+            pack_version: None,
+            mods: vec![ModReport::new(&jei, Some("2".into()), Outcome::Compatible(ReleaseStatus::Beta)), incompatible],
+        }
+    }
+
+    #[test]
+    fn picks_format_by_extension() {
+        assert_eq!(ReportFormat::for_path(Path::new("out.md")), ReportFormat::Markdown);
+        assert_eq!(ReportFormat::for_path(Path::new("out.HTML")), ReportFormat::Html);
+        assert_eq!(ReportFormat::for_path(Path::new("out.json")), ReportFormat::Json);
+        assert_eq!(ReportFormat::for_path(Path::new("out")), ReportFormat::Json);
+    }
+
+    #[test]
+    fn renders_markdown_tables() {
+        let markdown = render(&report(), ReportFormat::Markdown);
+        assert!(markdown.contains("1 of 2 mods are ready."));
+        assert!(markdown.contains("| jei | 1 | 2 | compatible (beta) | <https://www.curseforge.com/minecraft/mc-mods/jei/> |"));
+        assert!(markdown.contains("- [Bauble <Box>](https://www.curseforge.com/minecraft/mc-mods/bauble-box): Holds \\| things"));
+    }
+
+    #[test]
+    fn escapes_html() {
+        let html = render(&report(), ReportFormat::Html);
+        assert!(html.contains("Bauble &lt;Box&gt;"));
+        assert!(!html.contains("<Box>"));
+    }
+}
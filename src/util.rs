@@ -54,6 +54,22 @@ pub fn url_to_uri(url: &Url) -> Result<Uri, http::uri::InvalidUri> {
     Ok(Uri::from_str(url.as_ref())?)
 }
 
+///Serializes a `Uri` as a string, for use with `#[serde(with = "...")]`
+pub mod serde_uri {
+    use http::Uri;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(uri: &Uri, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(uri)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uri, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Uri::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 pub async fn save_stream_to_file<S>(
     mut stream: S,
     path: PathBuf,